@@ -1,53 +1,41 @@
-use hiven_rs::{Client, EventHandler, client::Error as ClientError, data::{House, Message}, gateway::EventInitState};
-use std::{future::Future, pin::Pin};
+use hiven_rs::{Client, Context, EventHandler, async_trait, client::Error as ClientError, data::{House, Message}, framework::Framework, gateway::EventInitState};
 use tokio::time::delay_for;
 
 #[tokio::main]
 async fn main() -> Result<(), ClientError> {
 	let client = Client::new("token");
-	client.start_gateway(MyEventHandler).await
+
+	let mut framework = Framework::new();
+	framework.prefix("$").help_command("help");
+	framework.command("hello", |context, message, _args| async move {
+		println!("I'm going to say hello back!");
+
+		context.trigger_typing(message.room_id).await?;
+		delay_for(std::time::Duration::from_millis(1000)).await;
+		context.send_message(message.room_id, "Hello!").await?;
+		Ok(())
+	}).help("Says hello back.");
+
+	let mut gate_keeper = client.new_gate_keeper(MyEventHandler);
+	gate_keeper.framework(framework);
+	gate_keeper.start_gateway().await
 }
 
 struct MyEventHandler;
 
+#[async_trait]
 impl EventHandler for MyEventHandler {
-	fn on_connect<'c>(&self, _client: &'c Client, event: EventInitState) ->
-			Pin<Box<dyn Future<Output = ()> + 'c>> {
-		let output = async move {
-			println!("I am @{}, also known as {}.", event.user.username, event.user.name);
-			//println!("{:?}", event);
-		};
-
-		Box::pin(output)
+	async fn on_connect(&self, _context: Context, event: EventInitState) {
+		println!("I am @{}, also known as {}.", event.user.username, event.user.name);
+		//println!("{:?}", event);
 	}
 
-	fn on_house_join<'c>(&self, _client: &'c Client, event: House) ->
-			Pin<Box<dyn Future<Output = ()> + 'c>> {
-		let output = async move {
-			println!("I just joined a house named {}.", event.name);
-			//println!("{:?}", event);
-		};
-
-		Box::pin(output)
+	async fn on_house_join(&self, _context: Context, event: House) {
+		println!("I just joined a house named {}.", event.name);
+		//println!("{:?}", event);
 	}
 
-	fn on_message<'c>(&self, client: &'c Client, event: Message) ->
-			Pin<Box<dyn Future<Output = ()> + 'c>> {
-		let output = async move {
-			println!("I just heard someone say {}.", event.content);
-
-			if event.content.starts_with("$") {match &event.content[1..] {
-				"hello" => {
-					println!("I'm going to say hello back!");
-
-					client.trigger_typing(event.room_id).await.unwrap();
-					delay_for(std::time::Duration::from_millis(1000)).await;
-					client.send_message(event.room_id, "Hello!".to_owned()).await.unwrap();
-				},
-				_ => ()
-			}}
-		};
-
-		Box::pin(output)
+	async fn on_message(&self, _context: Context, event: Message) {
+		println!("I just heard someone say {}.", event.content);
 	}
 }
@@ -0,0 +1,104 @@
+use self::super::{
+	http::FileUpload,
+	id::{MessageId, RoomId, UserId}
+};
+use std::fmt::Display;
+
+/// A message to send, composed of text, mentions, a reply and files.
+///
+/// Anything that can be sent with [Client::send_message] can be converted into
+/// a message builder, including plain strings.
+/// ```rust
+/// use hiven_rs::{builder::MessageBuilder, context::Context, data::Message};
+///
+/// # async fn greet(context: Context, message: Message) {
+/// let mut reply = MessageBuilder::new();
+/// reply.push("Hello, ").mention_user(message.author_id).push("!")
+///     .reply_to(&message);
+///
+/// context.send_message(message.room_id, reply).await.unwrap();
+/// # }
+/// ```
+///
+/// Hiven does not support sending embeds, links in the content of a message are
+/// embedded by hiven itself.
+///
+/// [Client::send_message]: ../client/struct.Client.html#method.send_message
+#[derive(Default)]
+pub struct MessageBuilder {
+	content: String,
+	reply_to: Option<MessageId>,
+	files: Vec<FileUpload>
+}
+
+impl MessageBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Appends text to the content of the message.
+	pub fn push<T>(&mut self, text: T) -> &mut Self
+			where T: Display {
+		self.content.push_str(&text.to_string());
+		self
+	}
+
+	/// Appends a mention of a user to the content of the message.
+	pub fn mention_user<U>(&mut self, user: U) -> &mut Self
+			where U: Into<UserId> {
+		self.push(format_args!("<@{}>", user.into()))
+	}
+
+	/// Appends a mention of a room to the content of the message.
+	pub fn mention_room<R>(&mut self, room: R) -> &mut Self
+			where R: Into<RoomId> {
+		self.push(format_args!("<#{}>", room.into()))
+	}
+
+	/// Makes the message a reply to another message.
+	pub fn reply_to<M>(&mut self, message: M) -> &mut Self
+			where M: Into<MessageId> {
+		self.reply_to = Some(message.into());
+		self
+	}
+
+	/// Attaches a file to the message.
+	pub fn file(&mut self, file: FileUpload) -> &mut Self {
+		self.files.push(file);
+		self
+	}
+
+	pub fn content(&self) -> &str {
+		&self.content
+	}
+
+	pub(crate) fn into_parts(self) ->
+			(String, Option<MessageId>, Vec<FileUpload>) {
+		(self.content, self.reply_to, self.files)
+	}
+}
+
+impl From<String> for MessageBuilder {
+	fn from(content: String) -> Self {
+		Self {content, ..Self::default()}
+	}
+}
+
+impl From<&str> for MessageBuilder {
+	fn from(content: &str) -> Self {
+		content.to_string().into()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn mention_test() {
+		let mut message = MessageBuilder::new();
+		message.push("Hi ").mention_user(UserId(1)).push(", see ")
+			.mention_room(RoomId(2));
+		assert_eq!(message.content(), "Hi <@1>, see <#2>");
+	}
+}
@@ -0,0 +1,88 @@
+use self::super::{
+	data::{House, PrivateRoom, Room, User},
+	gateway::OpCodeEvent,
+	id::{HouseId, RoomId, UserId}
+};
+use std::{collections::HashMap, sync::RwLock};
+
+/// A cache of the data received through a gateway connection, kept up to date
+/// as events are received.
+///
+/// Everything returned by the cache is a copy, so it may be outdated by the
+/// time it's looked at.
+#[derive(Debug, Default)]
+pub struct Cache {
+	houses: RwLock<HashMap<HouseId, House>>,
+	rooms: RwLock<HashMap<RoomId, Room>>,
+	private_rooms: RwLock<HashMap<RoomId, PrivateRoom>>,
+	users: RwLock<HashMap<UserId, User>>
+}
+
+impl Cache {
+	pub fn house(&self, id: HouseId) -> Option<House> {
+		self.houses.read().unwrap().get(&id).cloned()
+	}
+
+	pub fn houses(&self) -> Vec<House> {
+		self.houses.read().unwrap().values().cloned().collect()
+	}
+
+	pub fn room(&self, id: RoomId) -> Option<Room> {
+		self.rooms.read().unwrap().get(&id).cloned()
+	}
+
+	pub fn private_room(&self, id: RoomId) -> Option<PrivateRoom> {
+		self.private_rooms.read().unwrap().get(&id).cloned()
+	}
+
+	pub fn private_rooms(&self) -> Vec<PrivateRoom> {
+		self.private_rooms.read().unwrap().values().cloned().collect()
+	}
+
+	pub fn user(&self, id: UserId) -> Option<User> {
+		self.users.read().unwrap().get(&id).cloned()
+	}
+
+	/// Updates the cache with the data of an event.
+	pub(crate) fn update(&self, event: &OpCodeEvent) {
+		match event {
+			OpCodeEvent::InitState(data) => {
+				self.insert_user(&data.user);
+				for room in &data.private_rooms {self.insert_private_room(room)}
+			},
+			OpCodeEvent::HouseJoin(house) => {
+				let mut rooms = self.rooms.write().unwrap();
+				for room in &house.rooms {rooms.insert(room.id, room.clone());}
+				for member in &house.members {self.insert_user(&member.user)}
+
+				self.houses.write().unwrap().insert(house.id, house.clone());
+			},
+			OpCodeEvent::HouseMemberLeave(leave) => {
+				if let Some(house) = self.houses.write().unwrap()
+						.get_mut(&leave.house_id) {
+					house.members.retain(|member| member.user.id != leave.user_id)
+				}
+			},
+			OpCodeEvent::MessageCreate(message) => {
+				if let Some(Some(author)) = &message.author {self.insert_user(author)}
+				for user in message.mentions.iter().flatten().flatten() {
+					self.insert_user(user)
+				}
+			},
+			OpCodeEvent::PrivateRoomCreate(room) |
+			OpCodeEvent::PrivateRoomUpdate(room) => self.insert_private_room(room),
+			_ => ()
+		}
+	}
+
+	fn insert_private_room(&self, room: &PrivateRoom) {
+		for user in room.recipients.iter().flatten().flatten() {
+			self.insert_user(user)
+		}
+		self.private_rooms.write().unwrap().insert(room.id, room.clone());
+	}
+
+	fn insert_user(&self, user: &User) {
+		self.users.write().unwrap().insert(user.id, user.clone());
+	}
+}
@@ -1,44 +1,61 @@
 use self::super::{
-	data::{House, Message},
+	builder::MessageBuilder,
+	context::{Context, GatewayState},
+	data::{Ban, ClientSettings, House, Message, PrivateRoom, User},
+	framework::Framework,
+	id::{HouseId, MessageId, RoomId, UserId},
+	middleware::{Middleware, Next},
 	gateway::{
-		EventInitState, EventTypingStart,
+		EventHouseMemberLeave, EventInitState, EventRelationshipUpdate,
+		EventTypingStart,
 		Frame,
 		OpCodeEvent, OpCodeHello, OpCodeLogin
 	},
 	http::{
-		PathInfo,
-		RequestInfo, RequestBodyInfo
+		FileUpload, PathInfo,
+		RequestInfo, RequestBodyInfo, ResponseInfo
 	},
 	util::join_first
 };
+use async_trait::async_trait;
 use async_tungstenite::{
 	tokio::connect_async as websocket_async,
 	tungstenite::{
-		Message as WebsocketMessage,
+		Error as WebsocketError, Message as WebsocketMessage,
 		protocol::frame::CloseFrame
 	}
 };
 use futures::{
 	channel::mpsc::{Receiver, SendError, Sender, channel},
-	sink::SinkExt, stream::StreamExt
+	future::{BoxFuture, FutureExt, JoinAll, join_all, ready},
+	sink::SinkExt, stream::{Stream, StreamExt}
 };
-use reqwest::{Client as HTTPClient, Error as ReqwestError};
+use reqwest::{
+	Client as HTTPClient, Error as ReqwestError, Response,
+	multipart::{Form, Part}
+};
+use serde::de::DeserializeOwned;
 use serde_json::{
 	Error as SerdeJSONError,
 	from_str as from_json, to_string as to_json
 };
 use std::{
+	any::Any,
+	collections::{HashMap, VecDeque},
 	fmt::Debug,
-	future::{Future, ready},
+	future::Future,
+	panic::AssertUnwindSafe,
 	pin::Pin,
 	result::Result as STDResult,
-	sync::Arc,
+	sync::{Arc, Mutex},
+	task::{Context as TaskContext, Poll},
 	thread::{JoinHandle, spawn},
-	time::Duration
+	time::{Duration, Instant}
 };
 use tokio::{
-	select,
-	sync::Notify,
+	join, select,
+	sync::{Notify, Semaphore},
+	task::{JoinHandle as TaskJoinHandle, spawn as spawn_task},
 	time::timeout
 };
 
@@ -60,7 +77,7 @@ type Result<T> = STDResult<T, Error>;
 /// - Go to the network tab on the Developer Tools window
 /// - Start typing in the room
 /// - Select the new `typing` request that appears. If two show up, select
-/// 	the one with a 200 status code
+///   the one with a 200 status code
 /// - Look for the `authorization` header under Request Headers, under Headers
 /// - The long string to the right is your token
 ///
@@ -69,38 +86,36 @@ type Result<T> = STDResult<T, Error>;
 /// with your password.** Another thing to keep in mind; it's always good
 /// etiquette to automate seperate accounts, dedicated for automation, rather
 /// than your own.
-pub struct Client<'u, 't> {
-	token: &'t str,
-	domains: (&'u str, &'u str),
+///
+/// Clients are cheap handles to shared state, and can be cloned freely to be
+/// moved into spawned tasks.
+#[derive(Clone)]
+pub struct Client {
+	token: Arc<str>,
+	domains: (Arc<str>, Arc<str>),
 	http_client: HTTPClient
 }
 
-impl<'u, 't> Client<'u, 't> {
+impl Client {
 	/// Creates a new client with an authentication token. Uses the official
 	/// hiven.io servers.
-	pub fn new(token: &'t str) -> Self {
-		Self {
-			token: token,
-			domains: ("api.hiven.io", "swarm-dev.hiven.io"),
-			http_client: HTTPClient::new()
-		}
+	pub fn new(token: &str) -> Self {
+		Self::new_at(token, "api.hiven.io", "swarm-dev.hiven.io")
 	}
 
 	/// Creates a new client with an authentication token, allows you to specify
 	/// a base domain for the api and gateway.
-	pub fn new_at(token: &'t str, api_base: &'u str, gateway_base: &'u str) ->
-			Self {
+	pub fn new_at(token: &str, api_base: &str, gateway_base: &str) -> Self {
 		Self {
-			token,
-			domains: (api_base, gateway_base),
+			token: token.into(),
+			domains: (api_base.into(), gateway_base.into()),
 			http_client: HTTPClient::new()
 		}
 	}
 
-	pub async fn new_gate_keeper<'c, E>(&'c self, event_handler: E) ->
-			GateKeeper<'c, 'u, 't, E>
-				where E: EventHandler {
-		GateKeeper::new(self, event_handler)
+	pub fn new_gate_keeper<E>(&self, event_handler: E) -> GateKeeper<E>
+			where E: EventHandler + 'static {
+		GateKeeper::new(self.clone(), event_handler)
 	}
 
 	/// Takes control of this thread, starting a connection to the gateway and
@@ -108,27 +123,29 @@ impl<'u, 't> Client<'u, 't> {
 	///
 	/// This method takes an event handler to handle all gateway events. Gateway
 	/// events you do not implement will default to a method that does nothing
-	/// (NoOp). Due to limitations with traits (and the async_trait macro), event
-	/// handlers are not marked as `async`, but are asynchronous in spirit.
+	/// (NoOp). Async functions in traits aren't supported by Rust yet, so
+	/// implementations must be marked with the [async_trait] attribute, which
+	/// this crate re-exports. Each event is dispatched onto it's own task, so
+	/// event handlers must be `Send`, and may only borrow the event handler.
 	/// Implementing an event listener can be done like this...
 	/// ```rust
-	/// use hiven_rs::{client::{Client, EventHandler}, data::Message};
-	/// use std::{future::Future, pin::Pin};
+	/// use hiven_rs::{async_trait, client::EventHandler, context::Context,
+	///     data::Message};
 	///
 	/// // ...
 	///
 	/// # struct MyEventHandler;
 	/// #
+	/// #[async_trait]
 	/// impl EventHandler for MyEventHandler {
-	/// 	fn on_message<'c>(&self, client: &'c Client, event: Message) ->
-	/// 			Pin<Box<dyn Future<Output = ()> + 'c>> {
-	/// 		Box::pin(async move {
-	/// 			// Asynchronous code goes here.
-	/// 		})
-	/// 	}
+	///     async fn on_message(&self, context: Context, event: Message) {
+	///         // Asynchronous code goes here.
+	///     }
 	/// }
 	/// ```
 	///
+	/// [async_trait]: ../attr.async_trait.html
+	///
 	/// This method currently is not expected to return ever, unless during panic
 	/// unwinding, and it's return value should be treated as `!` (the never
 	/// type).
@@ -139,64 +156,308 @@ impl<'u, 't> Client<'u, 't> {
 	// will be changed to support returning of Errors, or a unit, with
 	// the introduction of a stop function.
 	pub async fn start_gateway<E>(&self, event_handler: E) -> Result<()>
-			where E: EventHandler {
-		let gate_keeper = GateKeeper::new(self, event_handler);
+			where E: EventHandler + 'static {
+		let gate_keeper = GateKeeper::new(self.clone(), event_handler);
 		gate_keeper.start_gateway().await
 	}
 
-	pub async fn send_message<R>(&self, room: R, content: String) -> Result<()>
-			where R: Into<u64> {
-		execute_request(&self.http_client, RequestInfo {
-			token: self.token.to_owned(),
-			path: PathInfo::MessageSend {
-				channel_id: room.into()
-			},
-			body: RequestBodyInfo::MessageSend {content}
-		}, self.domains.0).await
+	/// Sends a message to a room. Plain text can be sent as is, while mentions,
+	/// replies and files can be composed with a [MessageBuilder].
+	///
+	/// [MessageBuilder]: ../builder/struct.MessageBuilder.html
+	pub async fn send_message<R, M>(&self, room: R, message: M) -> Result<()>
+			where R: Into<RoomId>, M: Into<MessageBuilder> {
+		let (content, reply_to_id, files) = message.into().into_parts();
+		let body = RequestBodyInfo::MessageSend {content, reply_to_id};
+		if files.is_empty() {
+			return execute_request(&self.http_client, RequestInfo {
+				token: self.token.to_string(),
+				path: PathInfo::MessageSend {
+					channel_id: room.into()
+				},
+				body
+			}, &self.domains.0).await
+		}
+
+		let form = files.into_iter().fold(Form::new(), |form, file|
+			form.part("file", file.into_part()))
+			.part("json", Part::text(to_json(&body)?)
+				.mime_str("application/json")?);
+
+		let path = PathInfo::MediaMessageSend {channel_id: room.into()};
+		execute_upload(&self.http_client, &self.token, path, form,
+			&self.domains.0).await
+	}
+
+	/// Sends a message with files attached to it, uploading every file.
+	///
+	/// See [FileUpload] for the ways files can be uploaded.
+	///
+	/// [FileUpload]: ../http/struct.FileUpload.html
+	pub async fn send_files<R, C>(&self, room: R, content: C,
+			files: Vec<FileUpload>) -> Result<()>
+				where R: Into<RoomId>, C: Into<String> {
+		let mut message = MessageBuilder::from(content.into());
+		for file in files {message.file(file);}
+		self.send_message(room, message).await
 	}
 
-	pub async fn edit_message(&self, room: impl Into<u64>,
-			message: impl Into<u64>, content: String) -> Result<()> {
+	pub async fn edit_message(&self, room: impl Into<RoomId>,
+			message: impl Into<MessageId>, content: String) -> Result<()> {
 		execute_request(&self.http_client, RequestInfo {
-			token: self.token.to_owned(),
+			token: self.token.to_string(),
 			path: PathInfo::MessageEditDelete {
 				channel_id: room.into(),
 				message_id: message.into()
 			},
-			body: RequestBodyInfo::MessageSend {content}
-		}, self.domains.0).await
+			body: RequestBodyInfo::MessageSend {content, reply_to_id: None}
+		}, &self.domains.0).await
 	}
 
-	pub async fn delete_message(&self, room: impl Into<u64>,
-			message: impl Into<u64>) -> Result<()> {
+	pub async fn delete_message(&self, room: impl Into<RoomId>,
+			message: impl Into<MessageId>) -> Result<()> {
 		execute_request(&self.http_client, RequestInfo {
-			token: self.token.to_owned(),
+			token: self.token.to_string(),
 			path: PathInfo::MessageEditDelete {
 				channel_id: room.into(),
 				message_id: message.into()
 			},
 			body: RequestBodyInfo::MessageDelete
-		}, self.domains.0).await
+		}, &self.domains.0).await
 	}
 
 	pub async fn trigger_typing<R>(&self, room: R) -> Result<()>
-			where R: Into<u64> {
+			where R: Into<RoomId> {
 		execute_request(&self.http_client, RequestInfo {
-			token: self.token.to_owned(),
+			token: self.token.to_string(),
 			path: PathInfo::TypingTrigger {
 				channel_id: room.into()
 			},
 			body: RequestBodyInfo::TypingTrigger {}
-		}, self.domains.0).await
+		}, &self.domains.0).await
+	}
+
+	pub async fn get_user<U>(&self, user: U) -> Result<User>
+			where U: Into<UserId> {
+		execute_request_for(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::User {user: user.into().to_string()},
+			body: RequestBodyInfo::Get
+		}, &self.domains.0).await
+	}
+
+	/// Fetches a user by their username. Names that aren't usernames but would
+	/// be read as part of the path, such as `@me` for the current user, are
+	/// rejected, use [Client::current_user] for that instead.
+	///
+	/// [Client::current_user]: struct.Client.html#method.current_user
+	pub async fn get_user_by_username(&self, username: &str) -> Result<User> {
+		if matches!(username, "" | "." | ".." | "@me")
+			{return Err(Error::expectation_failed("a username", username))}
+
+		execute_request_for(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::User {user: username.to_owned()},
+			body: RequestBodyInfo::Get
+		}, &self.domains.0).await
+	}
+
+	/// Fetches the user this client is authenticated as.
+	pub async fn current_user(&self) -> Result<User> {
+		execute_request_for(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::CurrentUser,
+			body: RequestBodyInfo::Get
+		}, &self.domains.0).await
+	}
+
+	/// Edits the profile of the user this client is authenticated as. Only the
+	/// fields that are `Some` are changed.
+	pub async fn edit_profile(&self, name: Option<String>,
+			icon: Option<String>, header: Option<String>, bio: Option<String>) ->
+				Result<()> {
+		execute_request(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::CurrentUser,
+			body: RequestBodyInfo::ProfileEdit {name, icon, header, bio}
+		}, &self.domains.0).await
+	}
+
+	pub async fn get_settings(&self) -> Result<ClientSettings> {
+		execute_request_for(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::Settings,
+			body: RequestBodyInfo::Get
+		}, &self.domains.0).await
+	}
+
+	/// Updates the settings of the client. Only the fields that are set are
+	/// changed, see [ClientSettings].
+	///
+	/// [ClientSettings]: ../data/struct.ClientSettings.html
+	pub async fn update_settings(&self, settings: ClientSettings) -> Result<()> {
+		execute_request(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::Settings,
+			body: RequestBodyInfo::SettingsUpdate(settings)
+		}, &self.domains.0).await
+	}
+
+	/// Sends a friend request to a user. Sending a friend request to a user
+	/// that sent one accepts it.
+	pub async fn send_friend_request<U>(&self, user: U) -> Result<()>
+			where U: Into<UserId> {
+		execute_request(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::FriendRequests,
+			body: RequestBodyInfo::FriendRequestSend {user_id: user.into()}
+		}, &self.domains.0).await
+	}
+
+	/// Accepts a friend request a user sent.
+	pub async fn accept_friend_request<U>(&self, user: U) -> Result<()>
+			where U: Into<UserId> {
+		self.send_friend_request(user).await
+	}
+
+	/// Declines a friend request a user sent, or cancels one sent to them.
+	pub async fn decline_friend_request<U>(&self, user: U) -> Result<()>
+			where U: Into<UserId> {
+		execute_request(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::FriendRequest {user_id: user.into()},
+			body: RequestBodyInfo::RelationshipDelete
+		}, &self.domains.0).await
+	}
+
+	pub async fn remove_friend<U>(&self, user: U) -> Result<()>
+			where U: Into<UserId> {
+		execute_request(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::Friend {user_id: user.into()},
+			body: RequestBodyInfo::RelationshipDelete
+		}, &self.domains.0).await
+	}
+
+	pub async fn block_user<U>(&self, user: U) -> Result<()>
+			where U: Into<UserId> {
+		execute_request(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::Block {user_id: user.into()},
+			body: RequestBodyInfo::UserBlock
+		}, &self.domains.0).await
+	}
+
+	pub async fn unblock_user<U>(&self, user: U) -> Result<()>
+			where U: Into<UserId> {
+		execute_request(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::Block {user_id: user.into()},
+			body: RequestBodyInfo::RelationshipDelete
+		}, &self.domains.0).await
+	}
+
+	/// Removes a member from a house. They can join it again.
+	pub async fn kick_member<H, U>(&self, house: H, user: U) -> Result<()>
+			where H: Into<HouseId>, U: Into<UserId> {
+		execute_request(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::Member {house_id: house.into(), user_id: user.into()},
+			body: RequestBodyInfo::MemberKick
+		}, &self.domains.0).await
+	}
+
+	/// Bans a user from a house, removing them from it if they're a member.
+	pub async fn ban_member<H, U>(&self, house: H, user: U,
+			reason: Option<String>) -> Result<()>
+				where H: Into<HouseId>, U: Into<UserId> {
+		execute_request(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::Ban {house_id: house.into(), user_id: user.into()},
+			body: RequestBodyInfo::MemberBan {reason}
+		}, &self.domains.0).await
+	}
+
+	pub async fn unban_member<H, U>(&self, house: H, user: U) -> Result<()>
+			where H: Into<HouseId>, U: Into<UserId> {
+		execute_request(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::Ban {house_id: house.into(), user_id: user.into()},
+			body: RequestBodyInfo::BanDelete
+		}, &self.domains.0).await
+	}
+
+	pub async fn list_bans<H>(&self, house: H) -> Result<Vec<Ban>>
+			where H: Into<HouseId> {
+		execute_request_for(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::Bans {house_id: house.into()},
+			body: RequestBodyInfo::Get
+		}, &self.domains.0).await
+	}
+
+	/// Lists every private room the user is in.
+	pub async fn private_rooms(&self) -> Result<Vec<PrivateRoom>> {
+		execute_request_for(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::PrivateRooms,
+			body: RequestBodyInfo::Get
+		}, &self.domains.0).await
+	}
+
+	/// Opens a direct message with a user, returning the existing one if there
+	/// is one already.
+	pub async fn create_private_room<U>(&self, user: U) -> Result<PrivateRoom>
+			where U: Into<UserId> {
+		execute_request_for(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::PrivateRooms,
+			body: RequestBodyInfo::PrivateRoomCreate {recipient: user.into()}
+		}, &self.domains.0).await
 	}
-}
 
-impl Client<'static, 'static> {
-	pub fn start_gateway_later<E>(self: Arc<Self>, event_handler: E) ->
-			JoinHandle<()>
+	/// Opens a group with every given user.
+	pub async fn create_group_room(&self, users: &[UserId]) ->
+			Result<PrivateRoom> {
+		execute_request_for(&self.http_client, RequestInfo {
+			token: self.token.to_string(),
+			path: PathInfo::PrivateRooms,
+			body: RequestBodyInfo::GroupRoomCreate {recipients: users.to_vec()}
+		}, &self.domains.0).await
+	}
+
+	/// Sends a message to a user directly, opening a direct message with them
+	/// if needed.
+	pub async fn send_direct_message<U, M>(&self, user: U, message: M) ->
+			Result<()> where U: Into<UserId>, M: Into<MessageBuilder> {
+		let room = self.create_private_room(user).await?;
+		self.send_message(room, message).await
+	}
+
+	/// Spawns a connection to the gateway onto the current tokio runtime,
+	/// dispatching gateway events asynchronously.
+	///
+	/// Unlike [start_gateway_later], no thread or runtime is created; the
+	/// gateway shares the runtime of the caller. The returned [JoinHandle]
+	/// resolves to the result of the gateway once it stops, and the returned
+	/// [GatewayHandle] can be used to stop the gateway.
+	///
+	/// This method panics if it is not called from within a tokio runtime.
+	///
+	/// [start_gateway_later]: #method.start_gateway_later
+	/// [JoinHandle]: https://docs.rs/tokio/0.2/tokio/task/struct.JoinHandle.html
+	/// [GatewayHandle]: struct.GatewayHandle.html
+	pub fn spawn_gateway<E>(&self, event_handler: E) ->
+			(TaskJoinHandle<Result<()>>, GatewayHandle)
 				where E: EventHandler + 'static {
+		GateKeeper::new(self.clone(), event_handler).spawn_gateway()
+	}
+
+	pub fn start_gateway_later<E>(&self, event_handler: E) -> JoinHandle<()>
+			where E: EventHandler + 'static {
+		let gate_keeper = GateKeeper::new(self.clone(), event_handler);
+
 		spawn(move || {
-			let gate_keeper = GateKeeper::new(&self, event_handler);
 			let mut runtime = tokio::runtime::Runtime::new().unwrap();
 			runtime.block_on(async {
 				gate_keeper.start_gateway().await.unwrap();
@@ -207,6 +468,20 @@ impl Client<'static, 'static> {
 
 async fn execute_request(client: &HTTPClient, request: RequestInfo,
 		base_url: &str) -> Result<()> {
+	send_request(client, request, base_url).await?;
+	Ok(())
+}
+
+/// Executes a request, returning the data hiven responded with.
+async fn execute_request_for<T>(client: &HTTPClient, request: RequestInfo,
+		base_url: &str) -> Result<T> where T: DeserializeOwned {
+	let response = send_request(client, request, base_url).await?;
+	let response: ResponseInfo<T> = from_json(&response.text().await?)?;
+	Ok(response.data)
+}
+
+async fn send_request(client: &HTTPClient, request: RequestInfo,
+		base_url: &str) -> Result<Response> {
 	let path = format!("https://{}/v1{}", base_url, request.path.path());
 	let http_request = client.request(request.body.method(), &path)
 		.header("authorization", request.token);
@@ -216,34 +491,373 @@ async fn execute_request(client: &HTTPClient, request: RequestInfo,
 			.body(to_json(&request.body)?)
 	} else {http_request};
 
-	http_request.send().await?.error_for_status()?;
+	Ok(http_request.send().await?.error_for_status()?)
+}
+
+async fn execute_upload(client: &HTTPClient, token: &str, path: PathInfo,
+		form: Form, base_url: &str) -> Result<()> {
+	let path = format!("https://{}/v1{}", base_url, path.path());
+	client.post(&path).header("authorization", token).multipart(form)
+		.send().await?.error_for_status()?;
 	Ok(())
 }
 
-// These lifetimes and this generic are a special set of generics, they are able
-// to describe the person reading them with 100% accuracy.
-//
-// I promise this wasn't intended, but now I love it.
-pub struct GateKeeper<'c, 'u, 't, E>
-		where E: EventHandler {
-	pub client: &'c Client<'u, 't>,
-	pub event_handler: E
+pub struct GateKeeper<E>
+		where E: EventHandler + 'static {
+	pub client: Client,
+	pub event_handler: Arc<E>,
+	listeners: Arc<Listeners>,
+	middleware: Arc<Vec<Arc<dyn Middleware>>>,
+	streams: Mutex<Vec<Sender<OpCodeEvent>>>,
+	state: Arc<GatewayState>,
+	ignore_self: bool,
+	ignore_bots: bool,
+	dispatch_mode: DispatchMode,
+	concurrency_limit: Option<usize>,
+	buffer_size: usize,
+	stop: Arc<Notify>
 }
 
-impl<'c, 'u, 't, E> GateKeeper<'c, 'u, 't, E>
-		where E: EventHandler {
-	pub fn new(client: &'c Client<'u, 't>, event_handler: E) -> Self {
-		Self {client, event_handler}
+impl<E> GateKeeper<E>
+		where E: EventHandler + 'static {
+	pub fn new(client: Client, event_handler: E) -> Self {
+		Self {
+			client,
+			event_handler: Arc::new(event_handler),
+			listeners: Arc::new(Listeners::default()),
+			middleware: Arc::new(Vec::new()),
+			streams: Mutex::new(Vec::new()),
+			state: Arc::new(GatewayState::default()),
+			ignore_self: true,
+			ignore_bots: true,
+			dispatch_mode: DispatchMode::default(),
+			concurrency_limit: None,
+			buffer_size: 5,
+			stop: Arc::new(Notify::new())
+		}
+	}
+
+	/// Sets the amount of frames or events that can be buffered by the internal
+	/// channels of the gateway connection, and by event streams created after
+	/// this call. Defaults to 5.
+	pub fn buffer_size(&mut self, buffer_size: usize) -> &mut Self {
+		self.buffer_size = buffer_size;
+		self
+	}
+
+	/// Creates a stream that yields every event received by this gate keeper,
+	/// as an alternative to event handlers and closure listeners. Events are
+	/// yielded in the order they were received.
+	///
+	/// Once the buffer of a stream fills up, the gateway waits for it to be
+	/// consumed, so streams should be polled regularly or dropped.
+	/// ```rust
+	/// use futures::stream::StreamExt;
+	/// use hiven_rs::{Client, gateway::OpCodeEvent};
+	///
+	/// # async fn run() {
+	/// # let client = Client::new("token");
+	/// let mut gate_keeper = client.new_gate_keeper(());
+	/// let mut events = gate_keeper.event_stream();
+	/// let (_gateway, _handle) = gate_keeper.spawn_gateway();
+	///
+	/// while let Some(event) = events.next().await {
+	///     if let OpCodeEvent::MessageCreate(message) = event {
+	///         println!("Someone said {}.", message.content);
+	///     }
+	/// }
+	/// # }
+	/// ```
+	pub fn event_stream(&mut self) -> EventStream {
+		let (sender, receiver) = channel(self.buffer_size);
+		self.streams.get_mut().unwrap().push(sender);
+		EventStream {receiver}
+	}
+
+	/// Creates a handle that can stop this gate keeper's gateway connection.
+	pub fn handle(&self) -> GatewayHandle {
+		GatewayHandle {stop: self.stop.clone()}
+	}
+
+	/// Registers a closure to be called whenever the gateway connects, alongside
+	/// [EventHandler::on_connect]. Any amount of listeners can be registered.
+	///
+	/// If you only need closures, `()` can be used as a NoOp event handler.
+	/// ```rust
+	/// use hiven_rs::Client;
+	///
+	/// # let client = Client::new("token");
+	/// let mut gate_keeper = client.new_gate_keeper(());
+	/// gate_keeper.on_connect(|_context, event| async move {
+	///     println!("I am @{}.", event.user.username);
+	/// });
+	/// ```
+	///
+	/// [EventHandler::on_connect]: trait.EventHandler.html#method.on_connect
+	pub fn on_connect<F, R>(&mut self, listener: F) -> &mut Self
+			where F: Fn(Context, EventInitState) -> R + Send + Sync + 'static,
+				R: Future<Output = ()> + Send + 'static {
+		Arc::make_mut(&mut self.listeners).connect
+			.push(Arc::new(move |context, event| listener(context, event).boxed()));
+		self
+	}
+
+	/// Registers a closure to be called whenever a house is joined, alongside
+	/// [EventHandler::on_house_join].
+	///
+	/// [EventHandler::on_house_join]: trait.EventHandler.html#method.on_house_join
+	pub fn on_house_join<F, R>(&mut self, listener: F) -> &mut Self
+			where F: Fn(Context, House) -> R + Send + Sync + 'static,
+				R: Future<Output = ()> + Send + 'static {
+		Arc::make_mut(&mut self.listeners).house_join
+			.push(Arc::new(move |context, event| listener(context, event).boxed()));
+		self
+	}
+
+	/// Registers a closure to be called whenever a member leaves a house,
+	/// alongside [EventHandler::on_house_member_leave].
+	///
+	/// [EventHandler::on_house_member_leave]:
+	/// trait.EventHandler.html#method.on_house_member_leave
+	pub fn on_house_member_leave<F, R>(&mut self, listener: F) -> &mut Self
+			where F: Fn(Context, EventHouseMemberLeave) -> R + Send + Sync +
+					'static,
+				R: Future<Output = ()> + Send + 'static {
+		Arc::make_mut(&mut self.listeners).house_member_leave
+			.push(Arc::new(move |context, event| listener(context, event).boxed()));
+		self
+	}
+
+	/// Registers a closure to be called whenever someone starts typing,
+	/// alongside [EventHandler::on_typing].
+	///
+	/// [EventHandler::on_typing]: trait.EventHandler.html#method.on_typing
+	pub fn on_typing<F, R>(&mut self, listener: F) -> &mut Self
+			where F: Fn(Context, EventTypingStart) -> R + Send + Sync + 'static,
+				R: Future<Output = ()> + Send + 'static {
+		Arc::make_mut(&mut self.listeners).typing
+			.push(Arc::new(move |context, event| listener(context, event).boxed()));
+		self
+	}
+
+	/// Registers a closure to be called whenever a message is received,
+	/// alongside [EventHandler::on_message].
+	///
+	/// [EventHandler::on_message]: trait.EventHandler.html#method.on_message
+	pub fn on_message<F, R>(&mut self, listener: F) -> &mut Self
+			where F: Fn(Context, Message) -> R + Send + Sync + 'static,
+				R: Future<Output = ()> + Send + 'static {
+		Arc::make_mut(&mut self.listeners).message
+			.push(Arc::new(move |context, event| listener(context, event).boxed()));
+		self
+	}
+
+	/// Registers a closure to be called whenever a private room is created,
+	/// alongside [EventHandler::on_private_room_create].
+	///
+	/// [EventHandler::on_private_room_create]:
+	/// trait.EventHandler.html#method.on_private_room_create
+	pub fn on_private_room_create<F, R>(&mut self, listener: F) -> &mut Self
+			where F: Fn(Context, PrivateRoom) -> R + Send + Sync + 'static,
+				R: Future<Output = ()> + Send + 'static {
+		Arc::make_mut(&mut self.listeners).private_room_create
+			.push(Arc::new(move |context, event| listener(context, event).boxed()));
+		self
+	}
+
+	/// Registers a closure to be called whenever a private room is updated,
+	/// alongside [EventHandler::on_private_room_update].
+	///
+	/// [EventHandler::on_private_room_update]:
+	/// trait.EventHandler.html#method.on_private_room_update
+	pub fn on_private_room_update<F, R>(&mut self, listener: F) -> &mut Self
+			where F: Fn(Context, PrivateRoom) -> R + Send + Sync + 'static,
+				R: Future<Output = ()> + Send + 'static {
+		Arc::make_mut(&mut self.listeners).private_room_update
+			.push(Arc::new(move |context, event| listener(context, event).boxed()));
+		self
+	}
+
+	/// Registers a closure to be called whenever a relationship with another
+	/// user changes, alongside [EventHandler::on_relationship_update].
+	///
+	/// [EventHandler::on_relationship_update]:
+	/// trait.EventHandler.html#method.on_relationship_update
+	pub fn on_relationship_update<F, R>(&mut self, listener: F) -> &mut Self
+			where F: Fn(Context, EventRelationshipUpdate) -> R + Send + Sync +
+					'static,
+				R: Future<Output = ()> + Send + 'static {
+		Arc::make_mut(&mut self.listeners).relationship_update
+			.push(Arc::new(move |context, event| listener(context, event).boxed()));
+		self
+	}
+
+	/// Adds middleware that wraps the dispatch of every event. Middleware is ran
+	/// in the order it was added in, see [Middleware].
+	///
+	/// [Middleware]: ../middleware/trait.Middleware.html
+	pub fn middleware<M>(&mut self, middleware: M) -> &mut Self
+			where M: Middleware + 'static {
+		Arc::make_mut(&mut self.middleware).push(Arc::new(middleware));
+		self
+	}
+
+	/// Registers a closure to be called whenever dispatching an event panics,
+	/// alongside [EventHandler::on_panic].
+	///
+	/// [EventHandler::on_panic]: trait.EventHandler.html#method.on_panic
+	pub fn on_panic<F, R>(&mut self, listener: F) -> &mut Self
+			where F: Fn(Context, DispatchPanic) -> R + Send + Sync + 'static,
+				R: Future<Output = ()> + Send + 'static {
+		Arc::make_mut(&mut self.listeners).panic
+			.push(Arc::new(move |context, panic| listener(context, panic).boxed()));
+		self
+	}
+
+	/// Sets whether messages and typing sent by the logged in user are dropped
+	/// before they reach anything else, so a bot doesn't respond to itself.
+	/// Defaults to true.
+	pub fn ignore_self(&mut self, ignore_self: bool) -> &mut Self {
+		self.ignore_self = ignore_self;
+		self
+	}
+
+	/// Sets whether messages and typing sent by bots are dropped before they
+	/// reach anything else, so bots don't respond to each other. A user is known
+	/// to be a bot from the author sent along with a message, or from the cache.
+	/// Defaults to true.
+	pub fn ignore_bots(&mut self, ignore_bots: bool) -> &mut Self {
+		self.ignore_bots = ignore_bots;
+		self
+	}
+
+	/// Inserts a value into the data shared with every [Context], replacing any
+	/// previous value of the same type. See [Context::data].
+	///
+	/// [Context]: ../context/struct.Context.html
+	/// [Context::data]: ../context/struct.Context.html#method.data
+	pub fn insert_data<T>(&mut self, value: T) -> &mut Self
+			where T: Any + Send + Sync {
+		self.state.data.write().unwrap().insert(value);
+		self
+	}
+
+	/// Sets how events are dispatched relative to one another, see
+	/// [DispatchMode]. Defaults to [DispatchMode::Concurrent].
+	///
+	/// [DispatchMode]: enum.DispatchMode.html
+	/// [DispatchMode::Concurrent]: enum.DispatchMode.html#variant.Concurrent
+	pub fn dispatch_mode(&mut self, dispatch_mode: DispatchMode) -> &mut Self {
+		self.dispatch_mode = dispatch_mode;
+		self
+	}
+
+	/// Sets how many events can be handled at once, or `None` for no limit.
+	/// Events over the limit wait for their turn, without holding up the
+	/// gateway. Defaults to `None`.
+	///
+	/// This method panics if the limit is `Some(0)`, as no event could ever be
+	/// handled.
+	pub fn concurrency_limit(&mut self, limit: Option<usize>) -> &mut Self {
+		assert_ne!(limit, Some(0), "concurrency limit must be at least 1");
+		self.concurrency_limit = limit;
+		self
+	}
+
+	/// Registers a command framework, that is passed every message received.
+	pub fn framework(&mut self, framework: Framework) -> &mut Self {
+		let framework = Arc::new(framework);
+		self.on_message(move |context, message| {
+			let framework = framework.clone();
+			async move {framework.dispatch(context, message).await}
+		})
+	}
+
+	/// Creates a future that dispatches an event through the middleware, to the
+	/// event handler and listeners.
+	fn dispatch(&self, event: OpCodeEvent) -> BoxFuture<'static, ()> {
+		let event_handler = self.event_handler.clone();
+		let listeners = self.listeners.clone();
+		let middleware = self.middleware.clone();
+		let context = Context::new(self.client.clone(), self.state.clone());
+
+		async move {
+			let endpoint = |context, event| dispatch_event(
+				event_handler.clone(), listeners.clone(), context, event).boxed();
+			let dispatch = Next::new(&middleware, &endpoint)
+				.run(context.clone(), event.clone());
+
+			// A panicking handler shouldn't take the gateway down with it.
+			let result = AssertUnwindSafe(dispatch).catch_unwind().await;
+			if let Err(payload) = result {
+				let panic = DispatchPanic::new(event, payload);
+				let listening = call_listeners(&listeners.panic, &context, &panic);
+				join!(event_handler.on_panic(context, panic), listening);
+			}
+		}.boxed()
+	}
+
+	/// Checks whether an event was caused by the logged in user.
+	fn is_self_authored(&self, event: &OpCodeEvent) -> bool {
+		let user = self.state.user.read().unwrap();
+		let user_id = match &*user {
+			Some(user) => user.id,
+			None => return false
+		};
+
+		match event {
+			OpCodeEvent::MessageCreate(message) => message.author_id == user_id,
+			OpCodeEvent::TypingStart(typing) => typing.user_id == user_id,
+			_ => false
+		}
+	}
+
+	/// Checks whether an event was caused by a bot.
+	fn is_bot_authored(&self, event: &OpCodeEvent) -> bool {
+		let is_bot = |user_id| self.state.cache.user(user_id)
+			.is_some_and(|user| user.is_bot());
+
+		match event {
+			OpCodeEvent::MessageCreate(message) => match &message.author {
+				Some(Some(author)) => author.is_bot(),
+				_ => is_bot(message.author_id)
+			},
+			OpCodeEvent::TypingStart(typing) => is_bot(typing.user_id),
+			_ => false
+		}
+	}
+
+	/// Spawns this gate keeper's gateway connection onto the current tokio
+	/// runtime. See [Client::spawn_gateway].
+	///
+	/// [Client::spawn_gateway]: struct.Client.html#method.spawn_gateway
+	pub fn spawn_gateway(self) -> (TaskJoinHandle<Result<()>>, GatewayHandle) {
+		let handle = self.handle();
+
+		let task = spawn_task(async move {
+			self.start_gateway().await
+		});
+
+		(task, handle)
 	}
 
 	pub async fn start_gateway(&self) -> Result<()> {
-		let (outgoing_send, outgoing_receive) = channel(5);
-		let (incoming_send, incoming_receive) = channel(5);
+		let (outgoing_send, outgoing_receive) = channel(self.buffer_size);
+		let (incoming_send, incoming_receive) = channel(self.buffer_size);
 
-		join_first!(
+		let result = join_first!(
 			self.manage_gateway(incoming_send, outgoing_receive),
 			self.listen_gateway(incoming_receive, outgoing_send)
-		)
+		);
+
+		self.end_streams();
+		result
+	}
+
+	/// Ends every collector and event stream, once the gateway stopped.
+	fn end_streams(&self) {
+		self.state.collectors.close();
+		self.streams.lock().unwrap().clear();
 	}
 
 	/// Starts the gateway websocket connection, and abstracts it as two async
@@ -254,26 +868,28 @@ impl<'c, 'u, 't, E> GateKeeper<'c, 'u, 't, E>
 	/// This method only returns once some external condition has been met. The
 	/// conditions are:
 	/// 1. Either one of the channel handle's channels dies (or both)
-	/// 	- Returns `Ok(())` in this case
-	/// 2. Data was received from the gateway that could not be parsed
-	/// 	- Returns `Err(_)` in this case
+	///     - Returns `Ok(())` in this case
+	/// 2. The gateway was stopped through a [GatewayHandle]
+	///     - Returns `Ok(())` in this case
+	/// 3. Data was received from the gateway that could not be parsed
+	///     - Returns `Err(_)` in this case
 	///
 	/// [Frame]: ../gateway/enum.Frame.html
+	/// [GatewayHandle]: struct.GatewayHandle.html
 	async fn manage_gateway(&self, mut sender: Sender<Frame>,
 			mut receiver: Receiver<Frame>) -> Result<()> {
 		let url = format!("wss://{}/socket", self.client.domains.1);
-		let mut socket = websocket_async(url).await.unwrap().0; // Remove unwrap().
+		let mut socket = websocket_async(url).await?.0;
+		let mut pinger = Pinger::default();
 
 		loop {
 			let incoming_frame = socket.next();
 			let outgoing_frame = receiver.next();
+			let stopped = self.stop.notified();
 
 			select! {
-				// Remove second unwrap().
-				// Consider removing first unwrap(). (Can tungstenite return a None
-				// before SocketClose?)
-				frame = incoming_frame => match frame.unwrap().unwrap() {
-					WebsocketMessage::Text(frame) => {
+				frame = incoming_frame => match frame {
+					Some(Ok(WebsocketMessage::Text(frame))) => {
 						// This is to ignore invalid events, because not all events are
 						// coded in, and these events will return errors on deserialization.
 						let frame = match from_json(&frame) {
@@ -282,24 +898,49 @@ impl<'c, 'u, 't, E> GateKeeper<'c, 'u, 't, E>
 						};
 						//let frame = from_json(&frame)?;
 
-						if let Err(_) = sender.send(frame).await {
+						if sender.send(frame).await.is_err() {
 							break Ok(()) // Channel died.
 						}
 					},
-					WebsocketMessage::Close(close_data) =>
+					Some(Ok(WebsocketMessage::Pong(payload))) =>
+							if let Some(latency) = pinger.pong(&payload) {
+						*self.state.latency.write().unwrap() = Some(latency)
+					},
+					// Tungstenite answers pings on it's own.
+					Some(Ok(WebsocketMessage::Ping(_))) => (),
+					Some(Ok(WebsocketMessage::Close(close_data))) =>
 						break Err(Error::SocketClose(close_data)),
-					frame =>
+					Some(Ok(frame)) =>
 						break Err(Error::expectation_failed("Text or Close frame", frame)),
+					Some(Err(error)) => break Err(error.into()),
+					None => break Err(Error::SocketClose(None))
 				},
 
 				frame = outgoing_frame => match frame {
 					Some(frame) => {
+						let heart_beat = matches!(frame, Frame::HeartBeat);
 						let frame = WebsocketMessage::Text(to_json(&frame)?);
-						if let Err(_) = socket.send(frame).await {
+						if socket.send(frame).await.is_err() {
 							break Ok(()) // Channel died.
 						}
+
+						// Hiven doesn't acknowledge heart beats, so latency is measured
+						// with a websocket ping sent alongside them.
+						if heart_beat {
+							let (ping, lost) = pinger.ping();
+							if lost {*self.state.latency.write().unwrap() = None}
+							if socket.send(WebsocketMessage::Ping(ping)).await.is_err() {
+								break Ok(()) // Channel died.
+							}
+						}
 					},
 					None => break Ok(()) // Channel died.
+				},
+
+				_ = stopped => {
+					// The connection is being dropped either way, ignore close errors.
+					let _ = socket.close(None).await;
+					break Ok(())
 				}
 			}
 		}
@@ -318,7 +959,7 @@ impl<'c, 'u, 't, E> GateKeeper<'c, 'u, 't, E>
 				let duration = Duration::from_millis(heart_beat.into());
 
 				async move {loop {
-					if let Ok(_) = timeout(duration, notifier.notified()).await
+					if timeout(duration, notifier.notified()).await.is_ok()
 						{break Ok(())}
 					if let Err(err) = sender.send(Frame::HeartBeat).await
 						{break Err(err.into())}
@@ -331,36 +972,347 @@ impl<'c, 'u, 't, E> GateKeeper<'c, 'u, 't, E>
 			None => return Ok(())
 		};
 
+		self.state.collectors.open();
 		let listener = async {
-			receiver.for_each_concurrent(None, |frame| async {match frame {
-				Frame::Event(event) => match event {
-					OpCodeEvent::InitState(data) =>
-						self.event_handler.on_connect(&self.client, data).await,
-					OpCodeEvent::HouseJoin(data) =>
-						self.event_handler.on_house_join(&self.client, data).await,
-					OpCodeEvent::TypingStart(data) =>
-						self.event_handler.on_typing(&self.client, data).await,
-					OpCodeEvent::MessageCreate(data) =>
-						self.event_handler.on_message(&self.client, data).await
-				},
-				_ => unimplemented!() // Remove unimplemented!().
-			}}).await;
+			let events = receiver.filter_map(|frame| async {
+				let event = match frame {
+					Frame::Event(event) => event,
+					// Nothing else is expected after the hello, a repeated hello or
+					// an op code without meaning here is ignored.
+					_ => return None
+				};
+
+				// Streams and collectors are fed before dispatch, to keep them in
+				// order.
+				if let OpCodeEvent::InitState(data) = &event {
+					*self.state.user.write().unwrap() = Some(data.user.clone())
+				}
+				if self.ignore_self && self.is_self_authored(&event) {return None}
+				if self.ignore_bots && self.is_bot_authored(&event) {return None}
+
+				self.state.cache.update(&event);
+
+				self.state.collectors.feed(&event);
+
+				let streams = {
+					let mut streams = self.streams.lock().unwrap();
+					streams.retain(|stream| !stream.is_closed());
+					streams.clone()
+				};
+				for mut stream in streams {
+					// A stream dropped since doesn't affect anything else, ignore it.
+					let _ = stream.send(event.clone()).await;
+				}
+
+				Some(event)
+			});
+
+			// Dispatching never waits on handlers, so events keep flowing to
+			// collectors and streams however busy the handlers are.
+			let dispatcher =
+				Dispatcher::new(self.dispatch_mode, self.concurrency_limit);
+			events.for_each(|event| {
+				let key = self.dispatch_mode.key(&event);
+				dispatcher.dispatch(key, self.dispatch(event));
+				ready(())
+			}).await;
+			// Handlers waiting on collectors would otherwise never finish.
+			self.end_streams();
+			dispatcher.finish().await;
 
 			notifier.notify();
 			Ok(())
 		};
 
-		let token = self.client.token.to_owned();
+		let token = self.client.token.to_string();
 		sender.send(Frame::Login(OpCodeLogin {token})).await?;
 
 		join_first!(listener, heart_beat)
 	}
 }
 
+/// How events are dispatched, relative to one another.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DispatchMode {
+	/// Every event is dispatched as soon as it's received, so events can be
+	/// handled out of order.
+	#[default]
+	Concurrent,
+	/// Events of the same room are dispatched one after another, in the order
+	/// they were received. Events of different rooms are dispatched concurrently.
+	OrderedPerRoom,
+	/// Events of the same house are dispatched one after another, in the order
+	/// they were received. Events of different houses are dispatched
+	/// concurrently. Events that can't be traced to a house are ordered by room.
+	OrderedPerHouse
+}
+
+impl DispatchMode {
+	/// The key events are ordered by. Events without a room or house are ordered
+	/// together.
+	fn key(&self, event: &OpCodeEvent) -> Option<u64> {
+		match (self, event) {
+			(Self::OrderedPerHouse, OpCodeEvent::MessageCreate(message)) =>
+				Some(message.house_id.flatten()
+					.map_or(message.room_id.0, u64::from)),
+			(Self::OrderedPerHouse, OpCodeEvent::HouseJoin(house)) =>
+				Some(house.id.0),
+			(Self::OrderedPerHouse, OpCodeEvent::HouseMemberLeave(leave)) =>
+				Some(leave.house_id.0),
+			(_, OpCodeEvent::MessageCreate(message)) => Some(message.room_id.0),
+			(_, OpCodeEvent::TypingStart(typing)) => Some(typing.room_id.0),
+			(_, OpCodeEvent::PrivateRoomCreate(room)) |
+			(_, OpCodeEvent::PrivateRoomUpdate(room)) => Some(room.id.0),
+			_ => None
+		}
+	}
+}
+
+type Queues =
+	Arc<Mutex<HashMap<Option<u64>, VecDeque<BoxFuture<'static, ()>>>>>;
+
+/// Spawns the dispatch of events onto their own tasks, in the order and
+/// amount a [GateKeeper] is configured for.
+///
+/// [GateKeeper]: struct.GateKeeper.html
+struct Dispatcher {
+	mode: DispatchMode,
+	semaphore: Option<Arc<Semaphore>>,
+	/// The dispatches waiting in each key's queue. A queue only exists while
+	/// it's worker does, so idle rooms don't keep a task around.
+	queues: Queues,
+	/// Held by every task, to wait for all of them with.
+	running: (Sender<()>, Receiver<()>)
+}
+
+impl Dispatcher {
+	fn new(mode: DispatchMode, concurrency_limit: Option<usize>) -> Self {
+		Self {
+			mode,
+			semaphore: concurrency_limit.map(|limit| Arc::new(Semaphore::new(limit))),
+			queues: Queues::default(),
+			running: channel(0)
+		}
+	}
+
+	/// Spawns the dispatch of an event, without waiting for it. The key is only
+	/// used by ordered dispatch modes, see [DispatchMode::key].
+	///
+	/// [DispatchMode::key]: enum.DispatchMode.html#method.key
+	fn dispatch(&self, key: Option<u64>, dispatch: BoxFuture<'static, ()>) {
+		let semaphore = self.semaphore.clone();
+		let dispatch = async move {
+			let _permit = match semaphore {
+				Some(semaphore) => Some(semaphore.acquire_owned().await),
+				None => None
+			};
+			// Panics of the panic handlers themselves end up here, they mustn't
+			// take the worker of an ordered queue down with them.
+			let _ = AssertUnwindSafe(dispatch).catch_unwind().await;
+		}.boxed();
+
+		let running = self.running.0.clone();
+		if self.mode == DispatchMode::Concurrent {
+			spawn_task(async move {
+				dispatch.await;
+				drop(running)
+			});
+			return
+		}
+
+		// Every room (or house) gets it's own queue, that is worked through one
+		// event at a time by it's own task, until it's empty.
+		let mut queues = self.queues.lock().unwrap();
+		if let Some(queue) = queues.get_mut(&key) {
+			return queue.push_back(dispatch)
+		}
+
+		queues.insert(key, VecDeque::new());
+		let queues = self.queues.clone();
+		spawn_task(async move {
+			let mut dispatch = Some(dispatch);
+			while let Some(next) = dispatch {
+				next.await;
+
+				let mut queues = queues.lock().unwrap();
+				dispatch = queues.get_mut(&key).unwrap().pop_front();
+				if dispatch.is_none() {queues.remove(&key);}
+			}
+			drop(running)
+		});
+	}
+
+	/// Waits for every dispatch to finish.
+	async fn finish(self) {
+		let (running, mut finished) = self.running;
+		drop(running);
+		finished.next().await;
+	}
+}
+
+/// Dispatches an event to the corresponding method of an event handler, and
+/// to every closure listening for it. All of them are ran concurrently.
+async fn dispatch_event<E>(event_handler: Arc<E>, listeners: Arc<Listeners>,
+		context: Context, event: OpCodeEvent) where E: EventHandler {
+	match event {
+		OpCodeEvent::InitState(data) => {
+			let listening = call_listeners(&listeners.connect, &context, &data);
+			join!(event_handler.on_connect(context, data), listening);
+		},
+		OpCodeEvent::HouseJoin(data) => {
+			let listening = call_listeners(&listeners.house_join, &context, &data);
+			join!(event_handler.on_house_join(context, data), listening);
+		},
+		OpCodeEvent::HouseMemberLeave(data) => {
+			let listening =
+				call_listeners(&listeners.house_member_leave, &context, &data);
+			join!(event_handler.on_house_member_leave(context, data), listening);
+		},
+		OpCodeEvent::TypingStart(data) => {
+			let listening = call_listeners(&listeners.typing, &context, &data);
+			join!(event_handler.on_typing(context, data), listening);
+		},
+		OpCodeEvent::MessageCreate(data) => {
+			let listening = call_listeners(&listeners.message, &context, &data);
+			join!(event_handler.on_message(context, data), listening);
+		},
+		OpCodeEvent::PrivateRoomCreate(data) => {
+			let listening =
+				call_listeners(&listeners.private_room_create, &context, &data);
+			join!(event_handler.on_private_room_create(context, data), listening);
+		},
+		OpCodeEvent::PrivateRoomUpdate(data) => {
+			let listening =
+				call_listeners(&listeners.private_room_update, &context, &data);
+			join!(event_handler.on_private_room_update(context, data), listening);
+		},
+		OpCodeEvent::RelationshipUpdate(data) => {
+			let listening =
+				call_listeners(&listeners.relationship_update, &context, &data);
+			join!(event_handler.on_relationship_update(context, data), listening);
+		}
+	}
+}
+
+fn call_listeners<T>(listeners: &[Listener<T>], context: &Context, event: &T)
+		-> JoinAll<BoxFuture<'static, ()>> where T: Clone {
+	join_all(listeners.iter()
+		.map(|listener| listener(context.clone(), event.clone())))
+}
+
+type Listener<T> =
+	Arc<dyn Fn(Context, T) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Closures registered on a [GateKeeper], by event.
+///
+/// [GateKeeper]: struct.GateKeeper.html
+#[derive(Clone, Default)]
+struct Listeners {
+	connect: Vec<Listener<EventInitState>>,
+	house_join: Vec<Listener<House>>,
+	house_member_leave: Vec<Listener<EventHouseMemberLeave>>,
+	typing: Vec<Listener<EventTypingStart>>,
+	message: Vec<Listener<Message>>,
+	private_room_create: Vec<Listener<PrivateRoom>>,
+	private_room_update: Vec<Listener<PrivateRoom>>,
+	relationship_update: Vec<Listener<EventRelationshipUpdate>>,
+	panic: Vec<Listener<DispatchPanic>>
+}
+
+/// A panic that was caught while dispatching an event.
+#[derive(Clone, Debug)]
+pub struct DispatchPanic {
+	/// The event that was being dispatched.
+	pub event: OpCodeEvent,
+	/// The message the panic was started with.
+	pub message: String
+}
+
+impl DispatchPanic {
+	fn new(event: OpCodeEvent, payload: Box<dyn Any + Send>) -> Self {
+		let message = match payload.downcast::<String>() {
+			Ok(message) => *message,
+			Err(payload) => match payload.downcast::<&'static str>() {
+				Ok(message) => (*message).to_owned(),
+				Err(_) => "Box<dyn Any>".to_owned()
+			}
+		};
+
+		Self {event, message}
+	}
+}
+
+/// A stream of events received by a [GateKeeper], created with
+/// [GateKeeper::event_stream]. The stream ends once the gateway stops.
+///
+/// [GateKeeper]: struct.GateKeeper.html
+/// [GateKeeper::event_stream]: struct.GateKeeper.html#method.event_stream
+#[derive(Debug)]
+pub struct EventStream {
+	receiver: Receiver<OpCodeEvent>
+}
+
+impl Stream for EventStream {
+	type Item = OpCodeEvent;
+
+	fn poll_next(mut self: Pin<&mut Self>, context: &mut TaskContext) ->
+			Poll<Option<OpCodeEvent>> {
+		self.receiver.poll_next_unpin(context)
+	}
+}
+
+/// Measures latency with websocket pings. Each ping is numbered, so a late
+/// answer to a ping that was given up on isn't mistaken for a newer one.
+#[derive(Default)]
+struct Pinger {
+	/// The number of the last ping, and when it was sent, until it's answered.
+	sent: Option<(u64, Instant)>,
+	count: u64
+}
+
+impl Pinger {
+	/// Starts a new ping, returning it's payload, and whether the last ping was
+	/// lost, having not been answered before this one.
+	fn ping(&mut self) -> (Vec<u8>, bool) {
+		let lost = self.sent.is_some();
+		self.count += 1;
+		self.sent = Some((self.count, Instant::now()));
+		(self.count.to_be_bytes().to_vec(), lost)
+	}
+
+	/// Handles the answer to a ping, returning the latency if it answers the
+	/// last ping.
+	fn pong(&mut self, payload: &[u8]) -> Option<Duration> {
+		match self.sent {
+			Some((count, sent)) if payload == count.to_be_bytes() => {
+				self.sent = None;
+				Some(sent.elapsed())
+			},
+			_ => None
+		}
+	}
+}
+
+/// A handle to a running gateway connection, used to control it from outside
+/// of the event handler.
+#[derive(Clone, Debug)]
+pub struct GatewayHandle {
+	stop: Arc<Notify>
+}
+
+impl GatewayHandle {
+	/// Closes the gateway connection. The gateway finishes with `Ok(())` once
+	/// the connection has been closed.
+	pub fn stop(&self) {
+		self.stop.notify()
+	}
+}
+
 #[derive(Debug)]
 pub enum Error {
 	ExpectationFailed(&'static str, String),
 	SocketClose(Option<CloseFrame<'static>>),
+	WebSocket(WebsocketError),
 	HTTP(ReqwestError),
 	Serialization(SerdeJSONError),
 	InternalChannel
@@ -385,30 +1337,432 @@ impl From<SerdeJSONError> for Error {
 	}
 }
 
+impl From<WebsocketError> for Error {
+	fn from(error: WebsocketError) -> Self {
+		Self::WebSocket(error)
+	}
+}
+
 impl From<ReqwestError> for Error {
 	fn from(error: ReqwestError) -> Self {
 		Self::HTTP(error)
 	}
 }
 
-pub trait EventHandler: Send {
-	fn on_connect<'c>(&self, _client: &'c Client<'c, 'c>, _event: EventInitState) -> Pin<Box<dyn Future<Output = ()> + 'c>> {
+/// Handles events dispatched by a [GateKeeper].
+///
+/// Every method has a default implementation that does nothing, so only the
+/// events you're interested in need to be implemented. Implementations must be
+/// marked with the [async_trait] attribute.
+///
+/// [GateKeeper]: struct.GateKeeper.html
+/// [async_trait]: ../attr.async_trait.html
+#[async_trait]
+pub trait EventHandler: Send + Sync {
+	async fn on_connect(&self, _context: Context, _event: EventInitState) {
+		// NoOp
+	}
+
+	async fn on_house_join(&self, _context: Context, _event: House) {
 		// NoOp
-		Box::pin(ready(()))
 	}
 
-	fn on_house_join<'c>(&self, _client: &'c Client<'c, 'c>, _event: House) -> Pin<Box<dyn Future<Output = ()> + 'c>> {
+	/// Called when a member leaves a house, including when they're kicked or
+	/// banned.
+	async fn on_house_member_leave(&self, _context: Context,
+			_event: EventHouseMemberLeave) {
 		// NoOp
-		Box::pin(ready(()))
 	}
 
-	fn on_typing<'c>(&self, _client: &'c Client<'c, 'c>, _event: EventTypingStart) -> Pin<Box<dyn Future<Output = ()> + 'c>> {
+	async fn on_typing(&self, _context: Context, _event: EventTypingStart) {
 		// NoOp
-		Box::pin(ready(()))
 	}
 
-	fn on_message<'c>(&'c self, _client: &'c Client<'c, 'c>, _event: Message) -> Pin<Box<dyn Future<Output = ()> + 'c>> {
+	async fn on_message(&self, _context: Context, _event: Message) {
 		// NoOp
-		Box::pin(ready(()))
+	}
+
+	async fn on_private_room_create(&self, _context: Context,
+			_event: PrivateRoom) {
+		// NoOp
+	}
+
+	async fn on_private_room_update(&self, _context: Context,
+			_event: PrivateRoom) {
+		// NoOp
+	}
+
+	/// Called when a relationship with another user changes, such as when a
+	/// friend request is received or accepted.
+	async fn on_relationship_update(&self, _context: Context,
+			_event: EventRelationshipUpdate) {
+		// NoOp
+	}
+
+	/// Called when dispatching an event panics, be it in an event handler, a
+	/// listener or middleware. The gateway keeps running regardless.
+	async fn on_panic(&self, _context: Context, _panic: DispatchPanic) {
+		// NoOp
+	}
+}
+
+/// An event handler that handles nothing, for use with closure listeners.
+impl EventHandler for () {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use self::super::super::middleware::Filter;
+	use futures::channel::mpsc::{UnboundedSender, unbounded};
+	use serde_json::{from_value, json};
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use tokio::time::delay_for;
+
+	fn message(room: u64, content: &str) -> OpCodeEvent {
+		OpCodeEvent::MessageCreate(from_value(json!({"content": content,
+			"id": "1", "room_id": room.to_string(), "author_id": "2"})).unwrap())
+	}
+
+	fn content(event: &OpCodeEvent) -> String {
+		match event {
+			OpCodeEvent::MessageCreate(message) => message.content.clone(),
+			event => panic!("unexpected event {:?}", event)
+		}
+	}
+
+	struct Panicker(UnboundedSender<String>);
+
+	#[async_trait]
+	impl EventHandler for Panicker {
+		async fn on_message(&self, _context: Context, message: Message) {
+			if message.content == "panic" {panic!("handler panicked")}
+			self.0.unbounded_send(message.content).unwrap()
+		}
+
+		async fn on_panic(&self, _context: Context, panic: DispatchPanic) {
+			let report = format!("{}: {}", content(&panic.event), panic.message);
+			self.0.unbounded_send(report).unwrap()
+		}
+	}
+
+	#[tokio::test]
+	async fn panic_test() {
+		let (sender, mut receiver) = unbounded();
+		let client = Client::new("token");
+		let mut gate_keeper = client.new_gate_keeper(Panicker(sender));
+		let (panics, mut panic_receiver) = unbounded();
+		gate_keeper.on_panic(move |_context, panic| {
+			panics.unbounded_send(panic.message).unwrap();
+			ready(())
+		});
+
+		gate_keeper.dispatch(message(1, "panic")).await;
+		gate_keeper.dispatch(message(1, "after")).await;
+
+		assert_eq!(receiver.next().await.unwrap(), "panic: handler panicked");
+		assert_eq!(panic_receiver.next().await.unwrap(), "handler panicked");
+		assert_eq!(receiver.next().await.unwrap(), "after");
+	}
+
+	/// Middleware that logs the events passing through it, and stops the ones
+	/// that say it's stop word.
+	struct Logger(&'static str, &'static str, UnboundedSender<String>);
+
+	#[async_trait]
+	impl Middleware for Logger {
+		async fn handle(&self, context: Context, event: OpCodeEvent,
+				next: Next<'_>) {
+			self.2.unbounded_send(format!("{}: {}", self.0, content(&event)))
+				.unwrap();
+			if content(&event) != self.1 {next.run(context, event).await}
+		}
+	}
+
+	#[tokio::test]
+	async fn middleware_test() {
+		let (sender, receiver) = unbounded();
+		let client = Client::new("token");
+		let mut gate_keeper = client.new_gate_keeper(Panicker(sender.clone()));
+		let listened = sender.clone();
+		gate_keeper.on_message(move |_, message| {
+			listened.unbounded_send(format!("listener: {}", message.content))
+				.unwrap();
+			ready(())
+		});
+		gate_keeper.middleware(Logger("first", "", sender.clone()))
+			.middleware(Filter::new(|_, event| content(event) != "filtered"))
+			.middleware(Logger("second", "stop", sender));
+
+		for content in &["go", "stop", "filtered"]
+			{gate_keeper.dispatch(message(1, content)).await}
+		drop(gate_keeper);
+
+		let mut log = receiver.collect::<Vec<_>>().await;
+		// The handler and listeners run concurrently, in no particular order.
+		log[2..4].sort();
+		assert_eq!(log, [
+			"first: go", "second: go", "go", "listener: go",
+			"first: stop", "second: stop",
+			"first: filtered"
+		]);
+	}
+
+	#[tokio::test]
+	async fn username_test() {
+		let client = Client::new("token");
+		for username in &["", "..", "@me"] {
+			let result = client.get_user_by_username(username).await;
+			assert!(matches!(result, Err(Error::ExpectationFailed(..))));
+		}
+	}
+
+	#[test]
+	fn pinger_test() {
+		let mut pinger = Pinger::default();
+		let (first, lost) = pinger.ping();
+		assert!(!lost);
+		assert!(pinger.pong(&first).is_some());
+		assert!(pinger.pong(&first).is_none());
+
+		let (lost_ping, _) = pinger.ping();
+		let (second, lost) = pinger.ping();
+		assert!(lost);
+		// A late answer to the lost ping doesn't count for the newer one.
+		assert!(pinger.pong(&lost_ping).is_none());
+		assert!(pinger.pong(&second).is_some());
+	}
+
+	#[test]
+	#[should_panic]
+	fn zero_concurrency_limit_test() {
+		Client::new("token").new_gate_keeper(()).concurrency_limit(Some(0));
+	}
+
+	#[tokio::test]
+	async fn concurrency_limit_test() {
+		let dispatcher = Dispatcher::new(DispatchMode::Concurrent, Some(2));
+		let running = Arc::new((AtomicUsize::new(0), AtomicUsize::new(0)));
+
+		for _ in 0..10 {
+			let running = running.clone();
+			dispatcher.dispatch(None, async move {
+				let now = running.0.fetch_add(1, Ordering::SeqCst) + 1;
+				running.1.fetch_max(now, Ordering::SeqCst);
+				delay_for(Duration::from_millis(5)).await;
+				running.0.fetch_sub(1, Ordering::SeqCst);
+			}.boxed());
+		}
+
+		dispatcher.finish().await;
+		assert_eq!(running.1.load(Ordering::SeqCst), 2);
+	}
+
+	#[tokio::test]
+	async fn ordered_test() {
+		let dispatcher = Dispatcher::new(DispatchMode::OrderedPerRoom, None);
+		let queues = dispatcher.queues.clone();
+		let handled = Arc::new(Mutex::new(Vec::new()));
+
+		let events = [(1, "a", 20), (1, "b", 0), (2, "x", 0), (1, "c", 0)];
+		for (room, name, delay) in events {
+			let handled = handled.clone();
+			dispatcher.dispatch(Some(room), async move {
+				delay_for(Duration::from_millis(delay)).await;
+				handled.lock().unwrap().push(name);
+			}.boxed());
+		}
+
+		dispatcher.finish().await;
+		assert_eq!(*handled.lock().unwrap(), ["x", "a", "b", "c"]);
+		// Queues are removed along with their workers once they're worked through.
+		assert!(queues.lock().unwrap().is_empty());
+	}
+
+	#[tokio::test]
+	async fn ordered_panic_test() {
+		let dispatcher = Dispatcher::new(DispatchMode::OrderedPerRoom, None);
+		let queues = dispatcher.queues.clone();
+		let handled = Arc::new(AtomicUsize::new(0));
+
+		dispatcher.dispatch(Some(1), async {panic!("on_panic panicked")}.boxed());
+		for _ in 0..2 {
+			let handled = handled.clone();
+			dispatcher.dispatch(Some(1), async move {
+				handled.fetch_add(1, Ordering::SeqCst);
+			}.boxed());
+		}
+
+		dispatcher.finish().await;
+		assert_eq!(handled.load(Ordering::SeqCst), 2);
+		assert!(queues.lock().unwrap().is_empty());
+	}
+
+	#[tokio::test]
+	async fn limit_keeps_collectors_fed_test() {
+		let mut gate_keeper = Client::new("token").new_gate_keeper(());
+		let (replies, mut reply) = unbounded();
+		gate_keeper.concurrency_limit(Some(1));
+		gate_keeper.on_message(move |context, event| {
+			let replies = replies.clone();
+			async move {
+				if event.content != "question" {return}
+				// Only one event can be handled at once, and this one is still being
+				// handled while the answer is received.
+				let mut answers = context.collect_events(|event|
+					content(event) == "answer");
+				replies.unbounded_send(false).unwrap();
+				let answer = timeout(Duration::from_secs(5), answers.next()).await;
+				replies.unbounded_send(answer.is_ok()).unwrap();
+			}
+		});
+
+		let (mut incoming, receiver) = channel(5);
+		let (sender, _outgoing) = channel(5);
+		let feed = async {
+			let hello = Frame::Hello(OpCodeHello {heart_beat: 30000});
+			incoming.send(hello).await.unwrap();
+			incoming.send(Frame::Event(message(1, "question"))).await.unwrap();
+			assert_eq!(reply.next().await, Some(false)); // Waiting for the answer.
+			incoming.send(Frame::Event(message(1, "answer"))).await.unwrap();
+			incoming.close_channel();
+		};
+
+		let (result, _) = join!(gate_keeper.listen_gateway(receiver, sender), feed);
+		result.unwrap();
+		assert_eq!(reply.next().await, Some(true));
+	}
+
+	#[tokio::test]
+	async fn ignore_bots_test() {
+		async fn received(ignore_bots: bool) -> Vec<String> {
+			let mut gate_keeper = Client::new("token").new_gate_keeper(());
+			let (messages, received) = unbounded();
+			gate_keeper.ignore_bots(ignore_bots).on_message(move |_, message| {
+				messages.unbounded_send(message.content).unwrap();
+				ready(())
+			});
+
+			let bot = OpCodeEvent::MessageCreate(from_value(json!({
+				"content": "beep", "id": "1", "room_id": "1", "author_id": "3",
+				"author": {"username": "bot", "name": "Bot", "icon": null,
+					"header": null, "id": "3", "bot": true}
+			})).unwrap());
+			let (mut incoming, receiver) = channel(5);
+			let (sender, _outgoing) = channel(5);
+			let hello = Frame::Hello(OpCodeHello {heart_beat: 30000});
+			let human = message(1, "hi");
+			for frame in [hello, Frame::Event(bot), Frame::Event(human)]
+				{incoming.send(frame).await.unwrap()}
+			incoming.close_channel();
+
+			gate_keeper.listen_gateway(receiver, sender).await.unwrap();
+			drop(gate_keeper);
+			received.collect().await
+		}
+
+		assert_eq!(received(true).await, ["hi"]);
+		assert_eq!(received(false).await, ["beep", "hi"]);
+	}
+
+	#[tokio::test]
+	async fn ignore_self_test() {
+		async fn received(ignore_self: bool) -> Vec<String> {
+			let mut gate_keeper = Client::new("token").new_gate_keeper(());
+			let (messages, received) = unbounded();
+			gate_keeper.ignore_self(ignore_self).on_message(move |_, message| {
+				messages.unbounded_send(message.content).unwrap();
+				ready(())
+			});
+
+			let init = OpCodeEvent::InitState(from_value(json!({
+				"user": {"username": "me", "name": "Me", "icon": null,
+					"header": null, "id": "2"},
+				"settings": {}
+			})).unwrap());
+			let other = OpCodeEvent::MessageCreate(from_value(json!({
+				"content": "hi", "id": "1", "room_id": "1", "author_id": "3"
+			})).unwrap());
+			let (mut incoming, receiver) = channel(5);
+			let (sender, _outgoing) = channel(5);
+			let hello = Frame::Hello(OpCodeHello {heart_beat: 30000});
+			let own = message(1, "mine");
+			for frame in [hello, Frame::Event(init), Frame::Event(own),
+				Frame::Event(other)] {incoming.send(frame).await.unwrap()}
+			incoming.close_channel();
+
+			gate_keeper.listen_gateway(receiver, sender).await.unwrap();
+			drop(gate_keeper);
+			received.collect().await
+		}
+
+		assert_eq!(received(true).await, ["hi"]);
+		assert_eq!(received(false).await, ["mine", "hi"]);
+	}
+
+	#[tokio::test]
+	async fn event_stream_test() {
+		let mut gate_keeper = Client::new("token").new_gate_keeper(());
+		let events = gate_keeper.event_stream();
+		drop(gate_keeper.event_stream());
+
+		let (mut incoming, receiver) = channel(5);
+		let (sender, _outgoing) = channel(5);
+		let hello = Frame::Hello(OpCodeHello {heart_beat: 30000});
+		let (first, second) = (message(1, "a"), message(2, "b"));
+		for frame in [hello, Frame::Event(first), Frame::Event(second)]
+			{incoming.send(frame).await.unwrap()}
+		incoming.close_channel();
+
+		gate_keeper.listen_gateway(receiver, sender).await.unwrap();
+		// The gate keeper is kept, but the stream still ends with the gateway.
+		let events = timeout(Duration::from_secs(5),
+			events.map(|event| content(&event)).collect::<Vec<_>>()).await;
+		assert_eq!(events.unwrap(), ["a", "b"]);
+		assert!(gate_keeper.streams.lock().unwrap().is_empty());
+	}
+
+	#[tokio::test]
+	async fn unexpected_frame_test() {
+		let mut gate_keeper = Client::new("token").new_gate_keeper(());
+		let (messages, received) = unbounded();
+		gate_keeper.on_message(move |_, message| {
+			messages.unbounded_send(message.content).unwrap();
+			ready(())
+		});
+
+		let (mut incoming, receiver) = channel(5);
+		let (sender, _outgoing) = channel(5);
+		let hello = || Frame::Hello(OpCodeHello {heart_beat: 30000});
+		for frame in [hello(), hello(), Frame::HeartBeat,
+			Frame::Event(message(1, "hi"))] {incoming.send(frame).await.unwrap()}
+		incoming.close_channel();
+
+		gate_keeper.listen_gateway(receiver, sender).await.unwrap();
+		drop(gate_keeper);
+		assert_eq!(received.collect::<Vec<_>>().await, ["hi"]);
+	}
+
+	#[tokio::test]
+	async fn collector_end_test() {
+		let mut gate_keeper = Client::new("token").new_gate_keeper(());
+		let (replies, mut reply) = unbounded();
+		gate_keeper.on_message(move |context, _| {
+			let replies = replies.clone();
+			async move {
+				let mut never = context.collect_events(|_| false);
+				replies.unbounded_send(never.next().await.is_none()).unwrap();
+			}
+		});
+
+		let (mut incoming, receiver) = channel(5);
+		let (sender, _outgoing) = channel(5);
+		let hello = Frame::Hello(OpCodeHello {heart_beat: 30000});
+		for frame in [hello, Frame::Event(message(1, "hi"))]
+			{incoming.send(frame).await.unwrap()}
+		incoming.close_channel();
+
+		let listening = gate_keeper.listen_gateway(receiver, sender);
+		timeout(Duration::from_secs(5), listening).await.unwrap().unwrap();
+		assert_eq!(reply.next().await, Some(true));
 	}
 }
@@ -0,0 +1,125 @@
+use self::super::gateway::OpCodeEvent;
+use futures::{
+	channel::mpsc::{UnboundedReceiver, UnboundedSender, unbounded},
+	stream::{Stream, StreamExt}
+};
+use std::{
+	panic::{AssertUnwindSafe, catch_unwind},
+	pin::Pin,
+	sync::Mutex,
+	task::{Context as TaskContext, Poll}
+};
+
+type Filter = Box<dyn Fn(&OpCodeEvent) -> bool + Send + Sync>;
+type Waiting = (Filter, UnboundedSender<OpCodeEvent>);
+
+/// The collectors waiting on events of a gateway connection, or `None` once
+/// the connection stopped.
+pub(crate) struct Collectors {
+	waiting: Mutex<Option<Vec<Waiting>>>
+}
+
+impl Default for Collectors {
+	fn default() -> Self {
+		Self {waiting: Mutex::new(Some(Vec::new()))}
+	}
+}
+
+impl Collectors {
+	/// Registers a new collector, that receives every event matching the filter.
+	/// Collectors registered while the connection is stopped end right away.
+	pub fn collect<F>(&self, filter: F) -> EventCollector
+			where F: Fn(&OpCodeEvent) -> bool + Send + Sync + 'static {
+		let (sender, receiver) = unbounded();
+		if let Some(waiting) = &mut *self.waiting.lock().unwrap()
+			{waiting.push((Box::new(filter), sender))}
+		EventCollector {receiver}
+	}
+
+	/// Passes an event to every collector it matches, and forgets about
+	/// collectors that have been dropped. Collectors with a filter that panics
+	/// are ended.
+	pub fn feed(&self, event: &OpCodeEvent) {
+		let mut waiting = self.waiting.lock().unwrap();
+		let waiting = match &mut *waiting {
+			Some(waiting) => waiting,
+			None => return
+		};
+
+		waiting.retain(|(filter, sender)| {
+			if sender.is_closed() {return false}
+			match catch_unwind(AssertUnwindSafe(|| filter(event))) {
+				Ok(true) => sender.unbounded_send(event.clone()).is_ok(),
+				Ok(false) => true,
+				Err(_) => false
+			}
+		})
+	}
+
+	/// Allows collectors to be registered again, once a connection starts.
+	pub fn open(&self) {
+		self.waiting.lock().unwrap().get_or_insert_with(Vec::new);
+	}
+
+	/// Ends every collector, once a connection stopped.
+	pub fn close(&self) {
+		*self.waiting.lock().unwrap() = None
+	}
+}
+
+/// A stream of every event that matched a filter, created with
+/// [Context::collect_events].
+///
+/// Collecting stops once the collector is dropped, once [stop] is called or
+/// once the gateway stops.
+///
+/// [Context::collect_events]: ../context/struct.Context.html#method.collect_events
+/// [stop]: #method.stop
+#[derive(Debug)]
+pub struct EventCollector {
+	receiver: UnboundedReceiver<OpCodeEvent>
+}
+
+impl EventCollector {
+	/// Stops collecting events. Events that were already collected can still be
+	/// taken from the stream.
+	pub fn stop(&mut self) {
+		self.receiver.close()
+	}
+}
+
+impl Stream for EventCollector {
+	type Item = OpCodeEvent;
+
+	fn poll_next(mut self: Pin<&mut Self>, context: &mut TaskContext) ->
+			Poll<Option<OpCodeEvent>> {
+		self.receiver.poll_next_unpin(context)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::FutureExt;
+
+	#[test]
+	fn panicking_filter_test() {
+		let collectors = Collectors::default();
+		let mut panicking = collectors.collect(|_| panic!("filter panicked"));
+		let mut collecting = collectors.collect(|_| true);
+
+		let event = OpCodeEvent::TypingStart(serde_json::from_value(
+			serde_json::json!({"room_id": "1", "author_id": "2"})).unwrap());
+		collectors.feed(&event);
+		collectors.feed(&event);
+
+		assert!(panicking.next().now_or_never().unwrap().is_none());
+		assert!(collecting.next().now_or_never().unwrap().is_some());
+		assert!(collecting.next().now_or_never().unwrap().is_some());
+
+		collectors.close();
+		assert!(collecting.next().now_or_never().unwrap().is_none());
+		let mut late = collectors.collect(|_| true);
+		assert!(late.next().now_or_never().unwrap().is_none());
+	}
+}
@@ -0,0 +1,182 @@
+use self::super::{
+	cache::Cache,
+	client::Client,
+	collector::{Collectors, EventCollector},
+	data::{Message, User},
+	gateway::OpCodeEvent
+};
+use futures::stream::StreamExt;
+use std::{
+	any::{Any, TypeId},
+	collections::HashMap,
+	ops::Deref,
+	sync::{Arc, RwLock},
+	time::Duration
+};
+use tokio::time::timeout;
+
+/// The context an event was dispatched in, passed to every event handler and
+/// listener.
+///
+/// A context dereferences to the [Client] the gateway was started with, so API
+/// endpoints can be called on it directly.
+///
+/// [Client]: ../client/struct.Client.html
+#[derive(Clone)]
+pub struct Context {
+	pub client: Client,
+	state: Arc<GatewayState>
+}
+
+impl Context {
+	pub(crate) fn new(client: Client, state: Arc<GatewayState>) -> Self {
+		Self {client, state}
+	}
+
+	/// The user logged in to the gateway. Only `None` before the gateway has
+	/// connected.
+	pub fn user(&self) -> Option<User> {
+		self.state.user.read().unwrap().clone()
+	}
+
+	/// The cache of data received through the gateway.
+	pub fn cache(&self) -> &Cache {
+		&self.state.cache
+	}
+
+	/// The time it took the gateway to respond to the last ping. `None` before
+	/// the first ping has been answered, and after a ping went unanswered until
+	/// the next heart beat.
+	pub fn latency(&self) -> Option<Duration> {
+		*self.state.latency.read().unwrap()
+	}
+
+	/// Gets the value of the given type from the data shared between every
+	/// context, if there is one. This is useful for sharing database pools,
+	/// configuration and the like between event handlers.
+	/// ```rust
+	/// use hiven_rs::{Client, context::Context};
+	/// use std::sync::atomic::{AtomicUsize, Ordering};
+	///
+	/// struct MessageCount(AtomicUsize);
+	///
+	/// # let client = Client::new("token");
+	/// let mut gate_keeper = client.new_gate_keeper(());
+	/// gate_keeper.insert_data(MessageCount(AtomicUsize::new(0)));
+	/// gate_keeper.on_message(|context, _message| async move {
+	///     let count = context.data::<MessageCount>().unwrap();
+	///     count.0.fetch_add(1, Ordering::Relaxed);
+	/// });
+	/// ```
+	pub fn data<T>(&self) -> Option<Arc<T>>
+			where T: Any + Send + Sync {
+		self.state.data.read().unwrap().get()
+	}
+
+	/// Inserts a value into the data shared between every context, replacing any
+	/// previous value of the same type.
+	pub fn insert_data<T>(&self, value: T) -> Option<Arc<T>>
+			where T: Any + Send + Sync {
+		self.state.data.write().unwrap().insert(value)
+	}
+
+	/// Collects every event received from now on that matches the filter, until
+	/// the returned collector is dropped or stopped, or the gateway stops.
+	pub fn collect_events<F>(&self, filter: F) -> EventCollector
+			where F: Fn(&OpCodeEvent) -> bool + Send + Sync + 'static {
+		self.state.collectors.collect(filter)
+	}
+
+	/// Waits for the next event that matches the filter. Returns `None` if no
+	/// matching event was received before the timeout ran out, or if the gateway
+	/// stopped.
+	///
+	/// Waiting can be cancelled by dropping the returned future.
+	pub async fn wait_for<F>(&self, filter: F, duration: Duration) ->
+			Option<OpCodeEvent>
+				where F: Fn(&OpCodeEvent) -> bool + Send + Sync + 'static {
+		let mut collector = self.collect_events(filter);
+		timeout(duration, collector.next()).await.ok().flatten()
+	}
+
+	/// Waits for the next message that matches the predicate. Returns `None` if
+	/// no matching message was received before the timeout ran out.
+	/// ```rust
+	/// use hiven_rs::{context::Context, data::Message};
+	/// use std::time::Duration;
+	///
+	/// # async fn confirm(context: Context, message: Message) {
+	/// let (room, author) = (message.room_id, message.author_id);
+	/// let reply = context.wait_for_message(move |reply|
+	///     reply.room_id == room && reply.author_id == author,
+	///     Duration::from_secs(30)).await;
+	///
+	/// match reply {
+	///     Some(reply) if reply.content == "yes" => (), // Confirmed!
+	///     _ => () // Cancelled, or timed out.
+	/// }
+	/// # }
+	/// ```
+	pub async fn wait_for_message<F>(&self, predicate: F, duration: Duration) ->
+			Option<Message>
+				where F: Fn(&Message) -> bool + Send + Sync + 'static {
+		let event = self.wait_for(move |event| match event {
+			OpCodeEvent::MessageCreate(message) => predicate(message),
+			_ => false
+		}, duration).await;
+
+		match event {
+			Some(OpCodeEvent::MessageCreate(message)) => Some(message),
+			_ => None
+		}
+	}
+}
+
+impl Deref for Context {
+	type Target = Client;
+
+	fn deref(&self) -> &Client {
+		&self.client
+	}
+}
+
+/// The state of a gateway connection, shared between it's [GateKeeper] and
+/// every [Context] it creates.
+///
+/// [GateKeeper]: ../client/struct.GateKeeper.html
+/// [Context]: struct.Context.html
+#[derive(Default)]
+pub(crate) struct GatewayState {
+	pub collectors: Collectors,
+	pub user: RwLock<Option<User>>,
+	pub cache: Cache,
+	pub latency: RwLock<Option<Duration>>,
+	pub data: RwLock<TypeMap>
+}
+
+/// A map that holds at most one value of every type.
+#[derive(Debug, Default)]
+pub struct TypeMap {
+	values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>
+}
+
+impl TypeMap {
+	/// Inserts a value, returning the previous value of the same type.
+	pub fn insert<T>(&mut self, value: T) -> Option<Arc<T>>
+			where T: Any + Send + Sync {
+		self.values.insert(TypeId::of::<T>(), Arc::new(value))
+			.map(|value| value.downcast().unwrap())
+	}
+
+	pub fn get<T>(&self) -> Option<Arc<T>>
+			where T: Any + Send + Sync {
+		self.values.get(&TypeId::of::<T>())
+			.map(|value| value.clone().downcast().unwrap())
+	}
+
+	pub fn remove<T>(&mut self) -> Option<Arc<T>>
+			where T: Any + Send + Sync {
+		self.values.remove(&TypeId::of::<T>())
+			.map(|value| value.downcast().unwrap())
+	}
+}
@@ -1,83 +1,625 @@
-use self::super::util::{from_str, from_str_opt};
-use serde::{Deserialize, Serialize};
+use self::super::{
+	id::{HouseId, MessageId, RoomId, UserId},
+	util::double_option
+};
+use serde::{
+	Deserialize, Serialize,
+	de::{Deserializer, Error as DeserializeError, Unexpected, Visitor},
+	ser::Serializer
+};
+use serde_json::{Map, Value};
+use std::{
+	cmp::Ordering,
+	fmt::{Formatter, Result as FMTResult},
+	hash::{Hash, Hasher},
+	str::FromStr,
+	time::{Duration, SystemTime, UNIX_EPOCH}
+};
 
-#[derive(Debug, Deserialize, Serialize)]
+/// The fields hiven sent that a model doesn't know of, kept so the model can
+/// be serialized back to what was received, and so newer fields can be read
+/// before they're modeled.
+///
+/// Fields hiven may leave out are `Option<Option<T>>`, with `None` when the
+/// field was left out and `Some(None)` when it was null, so both are
+/// serialized back the way they were received.
+pub type Extra = Map<String, Value>;
+
+/// A point in time, which hiven sends as milliseconds since the unix epoch, as
+/// either an integer or a string. It's serialized back the way it was received.
+#[derive(Clone, Copy, Debug)]
+pub struct Timestamp {
+	millis: u64,
+	string: bool
+}
+
+impl Timestamp {
+	pub fn from_millis(millis: u64) -> Self {
+		Self {millis, string: false}
+	}
+
+	/// The milliseconds since the unix epoch.
+	pub fn millis(&self) -> u64 {
+		self.millis
+	}
+
+	pub fn system_time(&self) -> SystemTime {
+		UNIX_EPOCH + Duration::from_millis(self.millis)
+	}
+}
+
+impl From<SystemTime> for Timestamp {
+	fn from(time: SystemTime) -> Self {
+		let millis = time.duration_since(UNIX_EPOCH).unwrap_or_default()
+			.as_millis() as u64;
+		Self::from_millis(millis)
+	}
+}
+
+impl From<Timestamp> for SystemTime {
+	fn from(timestamp: Timestamp) -> SystemTime {
+		timestamp.system_time()
+	}
+}
+
+impl PartialEq for Timestamp {
+	fn eq(&self, other: &Self) -> bool {
+		self.millis == other.millis
+	}
+}
+
+impl Eq for Timestamp {}
+
+impl PartialOrd for Timestamp {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Timestamp {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.millis.cmp(&other.millis)
+	}
+}
+
+impl Hash for Timestamp {
+	fn hash<H>(&self, state: &mut H)
+			where H: Hasher {
+		self.millis.hash(state)
+	}
+}
+
+impl Serialize for Timestamp {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+			where S: Serializer {
+		match self.string {
+			true => serializer.collect_str(&self.millis),
+			false => serializer.serialize_u64(self.millis)
+		}
+	}
+}
+
+impl<'d> Deserialize<'d> for Timestamp {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+			where D: Deserializer<'d> {
+		deserializer.deserialize_any(TimestampVisitor)
+	}
+}
+
+struct TimestampVisitor;
+
+impl<'d> Visitor<'d> for TimestampVisitor {
+	type Value = Timestamp;
+
+	fn expecting(&self, formatter: &mut Formatter) -> FMTResult {
+		write!(formatter, "a timestamp in milliseconds, as a string or an integer")
+	}
+
+	fn visit_u64<E>(self, millis: u64) -> Result<Self::Value, E>
+			where E: DeserializeError {
+		Ok(Timestamp::from_millis(millis))
+	}
+
+	fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+			where E: DeserializeError {
+		let millis = u64::from_str(value).map_err(|_|
+			E::invalid_value(Unexpected::Str(value), &self))?;
+		Ok(Timestamp {millis, string: true})
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct House {
 	pub name: String,
-	pub icon: Option<String>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub icon: Option<Option<String>>,
 	pub members: Vec<Member>,
 	pub rooms: Vec<Room>,
-	#[serde(deserialize_with = "from_str")]
-	pub id: u64,
-	#[serde(deserialize_with = "from_str")]
-	pub owner_id: u64
+	pub id: HouseId,
+	pub owner_id: UserId,
+	#[serde(flatten)]
+	pub extra: Extra
+}
+
+impl From<House> for HouseId {
+	fn from(house: House) -> HouseId {
+		house.id
+	}
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl From<&House> for HouseId {
+	fn from(house: &House) -> HouseId {
+		house.id
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Member {
 	pub user: User,
 	//pub presence: Presence
+	#[serde(flatten)]
+	pub extra: Extra
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Room {
 	pub name: String,
-	pub description: Option<String>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub description: Option<Option<String>>,
 	//pub emoji:
 	pub position: usize,
-	#[serde(default)]
-	#[serde(deserialize_with = "from_str_opt")]
-	pub last_message_id: Option<u64>,
-	#[serde(deserialize_with = "from_str")]
-	pub id: u64
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub last_message_id: Option<Option<MessageId>>,
+	pub id: RoomId,
+	#[serde(flatten)]
+	pub extra: Extra
+}
+
+impl From<Room> for RoomId {
+	fn from(room: Room) -> RoomId {
+		room.id
+	}
 }
 
-impl From<Room> for u64 {
-	fn from(room: Room) -> u64 {
+impl From<&Room> for RoomId {
+	fn from(room: &Room) -> RoomId {
 		room.id
 	}
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// A room outside of any house, either a direct message with one user or a
+/// group with many.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PrivateRoom {
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub name: Option<Option<String>>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub recipients: Option<Option<Vec<User>>>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub last_message_id: Option<Option<MessageId>>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub owner_id: Option<Option<UserId>>,
+	pub id: RoomId,
+	#[serde(flatten)]
+	pub extra: Extra
+}
+
+impl PrivateRoom {
+	/// Whether this room is a group, rather than a direct message.
+	pub fn is_group(&self) -> bool {
+		self.recipients.iter().flatten().flatten().count() > 1
+	}
+}
+
+impl From<PrivateRoom> for RoomId {
+	fn from(room: PrivateRoom) -> RoomId {
+		room.id
+	}
+}
+
+impl From<&PrivateRoom> for RoomId {
+	fn from(room: &PrivateRoom) -> RoomId {
+		room.id
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Message {
 	pub content: String,
-	#[serde(deserialize_with = "from_str")]
-	pub id: u64,
-	#[serde(deserialize_with = "from_str")]
-	pub room_id: u64,
-	#[serde(deserialize_with = "from_str")]
-	pub author_id: u64
+	pub id: MessageId,
+	pub room_id: RoomId,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub house_id: Option<Option<HouseId>>,
+	pub author_id: UserId,
+	/// The author of the message, if it was sent along with it.
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub author: Option<Option<User>>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub timestamp: Option<Option<Timestamp>>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub edited_at: Option<Option<Timestamp>>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub mentions: Option<Option<Vec<User>>>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub attachment: Option<Option<Attachment>>,
+	/// The id of the message this message replies to.
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub reply_to_id: Option<Option<MessageId>>,
+	/// The message this message replies to, if it was sent along with it.
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub reply_to: Option<Option<Box<Message>>>,
+	#[serde(flatten)]
+	pub extra: Extra
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl From<Message> for MessageId {
+	fn from(message: Message) -> MessageId {
+		message.id
+	}
+}
+
+impl From<&Message> for MessageId {
+	fn from(message: &Message) -> MessageId {
+		message.id
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Attachment {
+	pub filename: String,
+	pub media_url: String,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub dimensions: Option<Option<Dimensions>>,
+	#[serde(flatten)]
+	pub extra: Extra
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Dimensions {
+	pub width: u32,
+	pub height: u32,
+	#[serde(default, rename = "type", deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub kind: Option<Option<String>>,
+	#[serde(flatten)]
+	pub extra: Extra
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct User {
 	pub username: String,
 	pub name: String,
-	pub icon: Option<String>,
-	pub header: Option<String>,
-	#[serde(deserialize_with = "from_str")]
-	pub id: u64
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub icon: Option<Option<String>>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub header: Option<Option<String>>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub bio: Option<Option<String>>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub bot: Option<Option<bool>>,
+	pub id: UserId,
+	#[serde(flatten)]
+	pub extra: Extra
+}
+
+impl User {
+	/// Whether the user is a bot. Users without a bot flag aren't.
+	pub fn is_bot(&self) -> bool {
+		self.bot.flatten().unwrap_or(false)
+	}
+}
+
+impl From<User> for UserId {
+	fn from(user: User) -> UserId {
+		user.id
+	}
+}
+
+impl From<&User> for UserId {
+	fn from(user: &User) -> UserId {
+		user.id
+	}
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// The settings of a client. Unset fields aren't serialized, so settings can
+/// also be used as a patch that only changes the fields that are set, where
+/// `Some(None)` resets a field.
+/// ```rust
+/// use hiven_rs::data::{ClientSettings, Theme};
+///
+/// let patch = ClientSettings {
+///     theme: Some(Some(Theme::Dark)),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct ClientSettings {
-	pub theme: Option<Theme>,
-	#[serde(rename = "enable_desktop_notifications")]
-	pub desktop_notifications: Option<bool>
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub theme: Option<Option<Theme>>,
+	#[serde(default, rename = "enable_desktop_notifications",
+		deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub desktop_notifications: Option<Option<bool>>,
+	#[serde(flatten)]
+	pub extra: Extra
+}
+
+/// A ban of a user from a house.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Ban {
+	pub user_id: UserId,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub user: Option<Option<User>>,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub reason: Option<Option<String>>,
+	#[serde(flatten)]
+	pub extra: Extra
+}
+
+/// A relationship with another user.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Relationship {
+	pub user_id: UserId,
+	#[serde(default, deserialize_with = "double_option",
+		skip_serializing_if = "Option::is_none")]
+	pub user: Option<Option<User>>,
+	#[serde(rename = "type")]
+	pub kind: RelationshipKind,
+	#[serde(flatten)]
+	pub extra: Extra
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub enum Presence {
-	#[serde(rename = "offline")]
-	Offline,
-	#[serde(rename = "online")]
-	Online
-	//?????
+/// The kind of a relationship with another user, which hiven sends as an
+/// integer.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RelationshipKind {
+	None,
+	/// A friend request was sent to the user, but not yet answered.
+	OutgoingRequest,
+	/// The user sent a friend request, that wasn't yet answered.
+	IncomingRequest,
+	Friend,
+	Blocked,
+	Unknown(u8)
+}
+
+impl From<u8> for RelationshipKind {
+	fn from(kind: u8) -> Self {
+		match kind {
+			0 => Self::None,
+			1 => Self::OutgoingRequest,
+			2 => Self::IncomingRequest,
+			3 => Self::Friend,
+			4 => Self::Blocked,
+			kind => Self::Unknown(kind)
+		}
+	}
+}
+
+impl From<RelationshipKind> for u8 {
+	fn from(kind: RelationshipKind) -> u8 {
+		match kind {
+			RelationshipKind::None => 0,
+			RelationshipKind::OutgoingRequest => 1,
+			RelationshipKind::IncomingRequest => 2,
+			RelationshipKind::Friend => 3,
+			RelationshipKind::Blocked => 4,
+			RelationshipKind::Unknown(kind) => kind
+		}
+	}
+}
+
+impl Serialize for RelationshipKind {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+			where S: Serializer {
+		serializer.serialize_u8((*self).into())
+	}
+}
+
+impl<'d> Deserialize<'d> for RelationshipKind {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+			where D: Deserializer<'d> {
+		u8::deserialize(deserializer).map(Self::from)
+	}
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub enum Theme {
-	#[serde(rename = "dark")]
-	Dark
-	//???????
+/// Declares an enum of the string values of a field, that falls back to
+/// `Unknown` for values it doesn't know of, so newer values hiven sends don't
+/// fail deserialization.
+macro_rules! string_enum {($(#[$attribute:meta])* $name:ident {
+	$($variant:ident = $value:literal),*
+}) => {
+	$(#[$attribute])*
+	#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+	pub enum $name {
+		$($variant,)*
+		Unknown(String)
+	}
+
+	impl $name {
+		pub fn as_str(&self) -> &str {
+			match self {
+				$(Self::$variant => $value,)*
+				Self::Unknown(value) => value
+			}
+		}
+	}
+
+	impl From<String> for $name {
+		fn from(value: String) -> Self {
+			match value.as_str() {
+				$($value => Self::$variant,)*
+				_ => Self::Unknown(value)
+			}
+		}
+	}
+
+	impl Serialize for $name {
+		fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+				where S: Serializer {
+			serializer.serialize_str(self.as_str())
+		}
+	}
+
+	impl<'d> Deserialize<'d> for $name {
+		fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+				where D: Deserializer<'d> {
+			String::deserialize(deserializer).map(Self::from)
+		}
+	}
+}}
+
+string_enum!(Presence {
+	Offline = "offline",
+	Online = "online"
+});
+
+string_enum!(Theme {
+	Dark = "dark"
+});
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::{
+		from_str as from_json, from_value, json, to_string as to_json, to_value
+	};
+	use std::time::{Duration, UNIX_EPOCH};
+
+	#[test]
+	fn message_test() {
+		let message: Message = from_json(r#"{"content": "Hi!", "id": "1",
+			"room_id": "2", "author_id": "3"}"#).unwrap();
+		assert!(message.timestamp.is_none() && message.mentions.is_none());
+
+		let message: Message = from_json(r#"{"content": "Hi!", "id": "1",
+			"room_id": "2", "author_id": "3", "timestamp": 1600000000000,
+			"edited_at": "1600000001000", "mentions": null}"#).unwrap();
+		let timestamp = UNIX_EPOCH + Duration::from_millis(1_600_000_000_000);
+		assert_eq!(message.timestamp, Some(Some(timestamp.into())));
+		assert_eq!(message.edited_at.flatten().unwrap().system_time(),
+			timestamp + Duration::from_secs(1));
+		assert!(matches!(message.mentions, Some(None)));
+	}
+
+	#[test]
+	fn round_trip_test() {
+		let json = json!({
+			"content": "Hi!", "id": "1", "room_id": "2", "house_id": null,
+			"author_id": "3", "timestamp": "1600000000000", "edited_at": null,
+			"mentions": null, "attachment": null, "reply_to_id": "4",
+			"author": {
+				"username": "someone", "name": "Someone", "icon": null,
+				"header": null, "bio": null, "id": "3", "bot": false
+			}
+		});
+		let message: Message = from_value(json.clone()).unwrap();
+		assert_eq!(to_value(&message).unwrap(), json);
+
+		let json = json!({"username": "someone", "name": "Someone",
+			"icon": "x.png", "header": null, "bio": null, "id": "3"});
+		let user: User = from_value(json.clone()).unwrap();
+		assert_eq!(to_value(&user).unwrap(), json);
+		let json = json!({"username": "someone", "name": "Someone", "id": "3"});
+		let user: User = from_value(json.clone()).unwrap();
+		assert_eq!(to_value(&user).unwrap(), json);
+
+		let json = json!({"name": "general", "description": null,
+			"position": 0, "last_message_id": null, "id": "1"});
+		let room: Room = from_value(json.clone()).unwrap();
+		assert_eq!(to_value(&room).unwrap(), json);
+		let json = json!({"name": "general", "position": 0, "id": "1"});
+		let room: Room = from_value(json.clone()).unwrap();
+		assert_eq!(to_value(&room).unwrap(), json);
+
+		let json = json!({"name": "House", "members": [], "rooms": [],
+			"id": "1", "owner_id": "2"});
+		let house: House = from_value(json.clone()).unwrap();
+		assert_eq!(to_value(&house).unwrap(), json);
+	}
+
+	#[test]
+	fn group_test() {
+		let user = json!({"username": "a", "name": "A", "icon": null,
+			"header": null, "id": "2"});
+		let is_group = |recipients: Option<Value>| {
+			let mut room = json!({"id": "1"});
+			if let Some(recipients) = recipients {room["recipients"] = recipients}
+			from_value::<PrivateRoom>(room).unwrap().is_group()
+		};
+
+		assert!(!is_group(None));
+		assert!(!is_group(Some(Value::Null)));
+		assert!(!is_group(Some(json!([user]))));
+		assert!(is_group(Some(json!([user, user]))));
+	}
+
+	#[test]
+	fn ban_test() {
+		let json = json!({"user_id": "1", "reason": null, "created_at": "x"});
+		let ban: Ban = from_value(json.clone()).unwrap();
+		assert_eq!(ban.user_id, UserId(1));
+		assert!(matches!((&ban.user, &ban.reason), (None, Some(None))));
+		assert_eq!(ban.extra["created_at"], "x");
+		assert_eq!(to_value(&ban).unwrap(), json);
+
+		let ban: Ban = from_value(json!({"user_id": "1", "reason": "spam",
+			"user": {"username": "a", "name": "A", "icon": null, "header": null,
+				"id": "1"}})).unwrap();
+		assert_eq!(ban.reason, Some(Some("spam".to_owned())));
+		assert_eq!(ban.user.flatten().unwrap().username, "a");
+	}
+
+	#[test]
+	fn unknown_enum_test() {
+		let theme: Theme = from_json("\"dark\"").unwrap();
+		assert_eq!(theme, Theme::Dark);
+		let theme: Theme = from_json("\"light\"").unwrap();
+		assert_eq!(theme, Theme::Unknown("light".into()));
+		assert_eq!(to_json(&theme).unwrap(), "\"light\"");
+	}
+
+	#[test]
+	fn settings_patch_test() {
+		let patch = ClientSettings {
+			theme: Some(Some(Theme::Dark)),
+			..Default::default()
+		};
+		assert_eq!(to_json(&patch).unwrap(), r#"{"theme":"dark"}"#);
+
+		let patch = ClientSettings {theme: Some(None), ..Default::default()};
+		assert_eq!(to_json(&patch).unwrap(), r#"{"theme":null}"#);
+	}
+
+	#[test]
+	fn extra_test() {
+		let json: Value = from_json(r#"{"name": "general", "description": null,
+			"emoji": "x", "position": 0, "id": "1"}"#).unwrap();
+		let room: Room = from_value(json.clone()).unwrap();
+		assert_eq!(room.extra["emoji"], "x");
+		assert_eq!(to_value(&room).unwrap(), json);
+	}
 }
@@ -0,0 +1,789 @@
+use self::super::{
+	client::Error as ClientError,
+	context::Context,
+	data::Message,
+	id::{HouseId, RoomId, UserId},
+	parse::{parse_user_mention, tokenize_spans}
+};
+use futures::future::{BoxFuture, FutureExt};
+use std::{
+	any::type_name,
+	collections::HashMap,
+	error::Error as StdError,
+	fmt::{Display, Formatter, Result as FMTResult},
+	future::Future,
+	str::FromStr,
+	sync::{Arc, Mutex, MutexGuard},
+	time::{Duration, Instant}
+};
+
+pub type CommandResult = Result<(), CommandError>;
+
+type Action =
+	Arc<dyn Fn(Context, Message, Args) -> BoxFuture<'static, CommandResult> +
+		Send + Sync>;
+type Check =
+	Arc<dyn Fn(Context, Message) -> BoxFuture<'static, Result<(), String>> +
+		Send + Sync>;
+type ErrorHandler =
+	Arc<dyn Fn(Context, Message, CommandError) -> BoxFuture<'static, ()> +
+		Send + Sync>;
+
+/// A command framework, a set of commands and the prefixes that invoke them.
+///
+/// A framework is given to a [GateKeeper], which passes it every message it
+/// receives.
+/// ```rust
+/// use hiven_rs::{Client, framework::Framework};
+///
+/// # let client = Client::new("token");
+/// let mut framework = Framework::new();
+/// framework.prefix("$").mention_prefix(true).help_command("help");
+/// framework.command("add", |context, message, mut args| async move {
+///     let sum = args.single::<i64>()? + args.single::<i64>()?;
+///     context.send_message(message.room_id, sum.to_string()).await?;
+///     Ok(())
+/// }).alias("plus").help("Adds two numbers together.").usage("<a> <b>");
+///
+/// let mut gate_keeper = client.new_gate_keeper(());
+/// gate_keeper.framework(framework);
+/// ```
+///
+/// [GateKeeper]: ../client/struct.GateKeeper.html
+#[derive(Default)]
+pub struct Framework {
+	prefixes: Vec<String>,
+	mention_prefix: bool,
+	owners: Vec<UserId>,
+	commands: Vec<Command>,
+	help_command: Option<String>,
+	error_handler: Option<ErrorHandler>
+}
+
+impl Framework {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a prefix that invokes commands. Any amount of prefixes can be added.
+	pub fn prefix(&mut self, prefix: &str) -> &mut Self {
+		self.prefixes.push(prefix.to_owned());
+		self
+	}
+
+	/// Sets whether mentioning the logged in user invokes commands, as if it were
+	/// a prefix. Defaults to false.
+	pub fn mention_prefix(&mut self, mention_prefix: bool) -> &mut Self {
+		self.mention_prefix = mention_prefix;
+		self
+	}
+
+	/// Adds users as owners, who are allowed to invoke commands marked as
+	/// [owners_only].
+	///
+	/// [owners_only]: struct.Command.html#method.owners_only
+	pub fn owners(&mut self, owners: &[UserId]) -> &mut Self {
+		self.owners.extend_from_slice(owners);
+		self
+	}
+
+	/// Adds a command, returning it so it can be configured further. The command
+	/// is invoked by it's name, or any of it's aliases, after a prefix.
+	pub fn command<F, R>(&mut self, name: &str, action: F) -> &mut Command
+			where F: Fn(Context, Message, Args) -> R + Send + Sync + 'static,
+				R: Future<Output = CommandResult> + Send + 'static {
+		self.commands.push(Command {
+			names: vec![name.to_owned()],
+			help: None,
+			usage: None,
+			allowed_houses: None,
+			allowed_rooms: None,
+			allowed_users: None,
+			owners_only: false,
+			checks: Vec::new(),
+			user_cooldown: None,
+			room_cooldown: None,
+			action: Arc::new(move |context, message, args|
+				action(context, message, args).boxed())
+		});
+
+		self.commands.last_mut().unwrap()
+	}
+
+	/// Enables a built in help command with the given name. It lists every
+	/// command when invoked alone, or describes the command it's given.
+	pub fn help_command(&mut self, name: &str) -> &mut Self {
+		self.help_command = Some(name.to_owned());
+		self
+	}
+
+	/// Sets the closure that is called whenever a command fails. If none is set,
+	/// errors are ignored.
+	pub fn on_error<F, R>(&mut self, handler: F) -> &mut Self
+			where F: Fn(Context, Message, CommandError) -> R + Send + Sync + 'static,
+				R: Future<Output = ()> + Send + 'static {
+		self.error_handler = Some(Arc::new(move |context, message, error|
+			handler(context, message, error).boxed()));
+		self
+	}
+
+	/// Finds a command by it's name or one of it's aliases.
+	pub fn find_command(&self, name: &str) -> Option<&Command> {
+		self.commands.iter()
+			.find(|command| command.names.iter().any(|alias| alias == name))
+	}
+
+	/// Strips a prefix from the start of some content, returning what's left.
+	fn strip_prefix<'c>(&self, context: &Context, content: &'c str) ->
+			Option<&'c str> {
+		let prefixed = self.prefixes.iter()
+			.find_map(|prefix| content.strip_prefix(prefix.as_str()));
+
+		prefixed.or_else(|| match context.user() {
+			Some(user) if self.mention_prefix =>
+				content.strip_prefix(&format!("<@{}>", user.id) as &str),
+			_ => None
+		})
+	}
+
+	/// Splits the content of a message into the name of the command it invokes
+	/// and it's arguments, if it starts with a prefix.
+	fn parse<'c>(&self, context: &Context, content: &'c str) ->
+			Option<(&'c str, Args)> {
+		let content = self.strip_prefix(context, content)?.trim_start();
+		let (name, rest) = match content.find(char::is_whitespace) {
+			Some(index) => (&content[..index], content[index..].trim_start()),
+			None => (content, "")
+		};
+
+		Some((name, Args::new(rest)))
+	}
+
+	/// Parses a message, and invokes the command it refers to, if any.
+	pub async fn dispatch(&self, context: Context, message: Message) {
+		let (name, args) = match self.parse(&context, &message.content) {
+			Some(parsed) => parsed,
+			None => return
+		};
+
+		let result = if self.help_command.as_deref() == Some(name) {
+			self.help(&context, &message, args).await
+		} else if let Some(command) = self.find_command(name) {
+			match command.run_checks(&self.owners, &context, &message).await {
+				Ok(()) =>
+					(command.action)(context.clone(), message.clone(), args).await,
+				Err(failure) => Err(CommandError::Check(failure))
+			}
+		} else {return};
+
+		if let (Err(error), Some(handler)) = (result, &self.error_handler) {
+			handler(context, message, error).await
+		}
+	}
+
+	/// The built in help command.
+	async fn help(&self, context: &Context, message: &Message, mut args: Args) ->
+			CommandResult {
+		let text = self.help_text(args.optional::<String>()?.as_deref());
+		context.send_message(message.room_id, text).await?;
+		Ok(())
+	}
+
+	/// The text of the help command, describing either the given command, or
+	/// listing every command.
+	fn help_text(&self, name: Option<&str>) -> String {
+		match name {
+			Some(name) => match self.find_command(name) {
+				Some(command) => command.describe(),
+				None => format!("There is no command named {}.", name)
+			},
+			None => self.commands.iter()
+				.map(|command| match &command.help {
+					Some(help) => format!("{} - {}", command.name(), help),
+					None => command.name().to_owned()
+				}).collect::<Vec<_>>().join("\n")
+		}
+	}
+}
+
+/// A command of a [Framework].
+///
+/// [Framework]: struct.Framework.html
+pub struct Command {
+	names: Vec<String>,
+	help: Option<String>,
+	usage: Option<String>,
+	allowed_houses: Option<Vec<HouseId>>,
+	allowed_rooms: Option<Vec<RoomId>>,
+	allowed_users: Option<Vec<UserId>>,
+	owners_only: bool,
+	checks: Vec<Check>,
+	user_cooldown: Option<Cooldown>,
+	room_cooldown: Option<Cooldown>,
+	action: Action
+}
+
+impl Command {
+	/// Adds an alternative name this command can be invoked with.
+	pub fn alias(&mut self, alias: &str) -> &mut Self {
+		self.names.push(alias.to_owned());
+		self
+	}
+
+	/// Sets the text describing this command in the help command.
+	pub fn help(&mut self, help: &str) -> &mut Self {
+		self.help = Some(help.to_owned());
+		self
+	}
+
+	/// Sets the text describing this command's arguments in the help command.
+	pub fn usage(&mut self, usage: &str) -> &mut Self {
+		self.usage = Some(usage.to_owned());
+		self
+	}
+
+	/// Restricts this command to the given houses. Can be called multiple times
+	/// to allow more houses.
+	pub fn allowed_houses(&mut self, houses: &[HouseId]) -> &mut Self {
+		self.allowed_houses.get_or_insert_with(Vec::new).extend_from_slice(houses);
+		self
+	}
+
+	/// Restricts this command to the given rooms.
+	pub fn allowed_rooms(&mut self, rooms: &[RoomId]) -> &mut Self {
+		self.allowed_rooms.get_or_insert_with(Vec::new).extend_from_slice(rooms);
+		self
+	}
+
+	/// Restricts this command to the given users.
+	pub fn allowed_users(&mut self, users: &[UserId]) -> &mut Self {
+		self.allowed_users.get_or_insert_with(Vec::new).extend_from_slice(users);
+		self
+	}
+
+	/// Restricts this command to the owners of the framework.
+	pub fn owners_only(&mut self) -> &mut Self {
+		self.owners_only = true;
+		self
+	}
+
+	/// Adds a custom check, that is ran before the command is. If the check
+	/// returns an error, the command is not invoked, and the error is passed to
+	/// the framework's error handler.
+	pub fn check<F, R>(&mut self, check: F) -> &mut Self
+			where F: Fn(Context, Message) -> R + Send + Sync + 'static,
+				R: Future<Output = Result<(), String>> + Send + 'static {
+		self.checks.push(Arc::new(move |context, message|
+			check(context, message).boxed()));
+		self
+	}
+
+	/// Sets how long each user must wait between invocations of this command.
+	pub fn user_cooldown(&mut self, duration: Duration) -> &mut Self {
+		self.user_cooldown = Some(Cooldown::new(duration));
+		self
+	}
+
+	/// Sets how long each room must wait between invocations of this command.
+	pub fn room_cooldown(&mut self, duration: Duration) -> &mut Self {
+		self.room_cooldown = Some(Cooldown::new(duration));
+		self
+	}
+
+	pub fn name(&self) -> &str {
+		&self.names[0]
+	}
+
+	pub fn aliases(&self) -> &[String] {
+		&self.names[1..]
+	}
+
+	/// Checks whether a message is allowed to invoke this command. Cooldowns are
+	/// only started if every other check passes.
+	async fn run_checks(&self, owners: &[UserId], context: &Context,
+			message: &Message) -> Result<(), CheckFailure> {
+		fn allowed<I>(allowed: &Option<Vec<I>>, id: Option<I>) -> bool
+				where I: PartialEq {
+			match (allowed, id) {
+				(Some(allowed), Some(id)) => allowed.contains(&id),
+				(Some(_), None) => false,
+				(None, _) => true
+			}
+		}
+
+		if !allowed(&self.allowed_houses, message.house_id.flatten())
+			{return Err(CheckFailure::House)}
+		if !allowed(&self.allowed_rooms, Some(message.room_id))
+			{return Err(CheckFailure::Room)}
+		if !allowed(&self.allowed_users, Some(message.author_id))
+			{return Err(CheckFailure::User)}
+		if self.owners_only && !owners.contains(&message.author_id)
+			{return Err(CheckFailure::OwnersOnly)}
+
+		for check in &self.checks {
+			check(context.clone(), message.clone()).await
+				.map_err(CheckFailure::Custom)?
+		}
+
+		// Both cooldowns are held until they're started, so neither is started
+		// when the other is running, and no other invocation can slip in between.
+		let now = Instant::now();
+		let user = self.user_cooldown.as_ref()
+			.map(|cooldown| cooldown.lock(message.author_id.0, now));
+		let room = self.room_cooldown.as_ref()
+			.map(|cooldown| cooldown.lock(message.room_id.0, now));
+
+		if let Some(left) = user.as_ref().and_then(|user| user.remaining(now))
+			{return Err(CheckFailure::UserCooldown(left))}
+		if let Some(left) = room.as_ref().and_then(|room| room.remaining(now))
+			{return Err(CheckFailure::RoomCooldown(left))}
+
+		if let Some(user) = user {user.start(now)}
+		if let Some(room) = room {room.start(now)}
+		Ok(())
+	}
+
+	/// Describes this command in full, for the help command.
+	fn describe(&self) -> String {
+		let mut text = match &self.usage {
+			Some(usage) => format!("{} {}", self.name(), usage),
+			None => self.name().to_owned()
+		};
+
+		if let Some(help) = &self.help {
+			text = format!("{}\n{}", text, help)
+		}
+		if !self.aliases().is_empty() {
+			text = format!("{}\nAliases: {}", text, self.aliases().join(", "))
+		}
+
+		text
+	}
+}
+
+/// A cooldown, tracked separately for every user or room.
+struct Cooldown {
+	duration: Duration,
+	last_used: Mutex<HashMap<u64, Instant>>
+}
+
+impl Cooldown {
+	fn new(duration: Duration) -> Self {
+		Self {duration, last_used: Mutex::new(HashMap::new())}
+	}
+
+	/// Locks the cooldown of the given id, so it can be checked and then started
+	/// without anything else starting it in between. Cooldowns that ran out are
+	/// forgotten.
+	fn lock(&self, id: u64, now: Instant) -> CooldownGuard<'_> {
+		let mut last_used = self.last_used.lock().unwrap();
+		let duration = self.duration;
+		last_used.retain(|_, used| now.duration_since(*used) < duration);
+		CooldownGuard {duration, last_used, id}
+	}
+}
+
+struct CooldownGuard<'c> {
+	duration: Duration,
+	last_used: MutexGuard<'c, HashMap<u64, Instant>>,
+	id: u64
+}
+
+impl CooldownGuard<'_> {
+	/// How much of the cooldown is left, if it's running.
+	fn remaining(&self, now: Instant) -> Option<Duration> {
+		let used = self.last_used.get(&self.id)?;
+		self.duration.checked_sub(now.duration_since(*used))
+			.filter(|left| *left > Duration::from_secs(0))
+	}
+
+	fn start(mut self, now: Instant) {
+		self.last_used.insert(self.id, now);
+	}
+}
+
+/// The arguments a command was invoked with, split by whitespace, with text in
+/// double quotes kept together as one argument.
+#[derive(Clone, Debug)]
+pub struct Args {
+	content: String,
+	/// Each argument, along with the index it starts at in the content.
+	arguments: Vec<(usize, String)>,
+	position: usize
+}
+
+impl Args {
+	pub fn new(content: &str) -> Self {
+		Self {
+			content: content.to_owned(),
+			arguments: tokenize_spans(content),
+			position: 0
+		}
+	}
+
+	/// Parses the next argument.
+	pub fn single<T>(&mut self) -> Result<T, ArgError>
+			where T: FromStr {
+		self.optional()?.ok_or_else(|| ArgError::Missing(short_type_name::<T>()))
+	}
+
+	/// Parses the next argument, if there is one.
+	pub fn optional<T>(&mut self) -> Result<Option<T>, ArgError>
+			where T: FromStr {
+		let argument = match self.arguments.get(self.position) {
+			Some((_, argument)) => argument,
+			None => return Ok(None)
+		};
+
+		let value = T::from_str(argument).map_err(|_|
+			ArgError::Invalid(argument.to_owned(), short_type_name::<T>()))?;
+		self.position += 1;
+		Ok(Some(value))
+	}
+
+	/// Parses the next argument as a user, from either a mention or an id.
+	pub fn user(&mut self) -> Result<UserId, ArgError> {
+		let (_, argument) = self.arguments.get(self.position)
+			.ok_or_else(|| ArgError::Missing(short_type_name::<UserId>()))?;
+
+		let user = parse_user_mention(argument)
+			.or_else(|| argument.parse().ok())
+			.ok_or_else(|| ArgError::Invalid(argument.to_owned(),
+				short_type_name::<UserId>()))?;
+		self.position += 1;
+		Ok(user)
+	}
+
+	/// Takes the text of every argument that is left, as it was written,
+	/// including quotes and spacing.
+	pub fn rest(&mut self) -> String {
+		let rest = match self.arguments.get(self.position) {
+			Some((start, _)) => self.content[*start..].trim_end().to_owned(),
+			None => String::new()
+		};
+		self.position = self.arguments.len();
+		rest
+	}
+
+	/// The amount of arguments that haven't been taken yet.
+	pub fn remaining(&self) -> usize {
+		self.arguments.len() - self.position
+	}
+}
+
+/// The name of a type without the paths of it's modules, so it can be shown
+/// to users. `Option<alloc::string::String>` becomes `Option<String>`.
+fn short_type_name<T>() -> String {
+	let mut name = String::new();
+	for part in type_name::<T>().split_inclusive(|character: char|
+			!(character.is_alphanumeric() || character == '_' || character == ':')) {
+		// Each part is a path, followed by at most one other character.
+		let start = part.rfind("::").map_or(0, |index| index + 2);
+		name.push_str(&part[start..]);
+	}
+	name
+}
+
+#[derive(Debug)]
+pub enum ArgError {
+	/// An argument of the given type was expected, but there were none left.
+	Missing(String),
+	/// An argument could not be parsed into the given type.
+	Invalid(String, String)
+}
+
+impl Display for ArgError {
+	fn fmt(&self, formatter: &mut Formatter) -> FMTResult {
+		match self {
+			Self::Missing(expected) =>
+				write!(formatter, "missing argument, expected {}", expected),
+			Self::Invalid(argument, expected) =>
+				write!(formatter, "invalid argument {}, expected {}", argument,
+					expected)
+		}
+	}
+}
+
+impl StdError for ArgError {}
+
+/// The reason a command was not allowed to be invoked.
+#[derive(Debug)]
+pub enum CheckFailure {
+	/// The command was invoked outside of it's allowed houses.
+	House,
+	/// The command was invoked outside of it's allowed rooms.
+	Room,
+	/// The command was invoked by a user that isn't allowed to.
+	User,
+	/// The command was invoked by a user that isn't an owner.
+	OwnersOnly,
+	/// The user's cooldown has the given amount of time left.
+	UserCooldown(Duration),
+	/// The room's cooldown has the given amount of time left.
+	RoomCooldown(Duration),
+	/// A custom check failed with the given reason.
+	Custom(String)
+}
+
+impl Display for CheckFailure {
+	fn fmt(&self, formatter: &mut Formatter) -> FMTResult {
+		match self {
+			Self::House => write!(formatter, "command is not allowed in this house"),
+			Self::Room => write!(formatter, "command is not allowed in this room"),
+			Self::User => write!(formatter, "user is not allowed to use command"),
+			Self::OwnersOnly => write!(formatter, "command is for owners only"),
+			Self::UserCooldown(left) | Self::RoomCooldown(left) =>
+				write!(formatter, "command is on cooldown for {:.1}s",
+					left.as_secs_f32()),
+			Self::Custom(reason) => write!(formatter, "{}", reason)
+		}
+	}
+}
+
+impl StdError for CheckFailure {}
+
+#[derive(Debug)]
+pub enum CommandError {
+	Argument(ArgError),
+	Check(CheckFailure),
+	Client(ClientError),
+	Other(String)
+}
+
+impl Display for CommandError {
+	fn fmt(&self, formatter: &mut Formatter) -> FMTResult {
+		match self {
+			Self::Argument(error) => write!(formatter, "{}", error),
+			Self::Check(failure) => write!(formatter, "{}", failure),
+			Self::Client(error) => write!(formatter, "request failed: {:?}", error),
+			Self::Other(error) => write!(formatter, "{}", error)
+		}
+	}
+}
+
+impl StdError for CommandError {
+	fn source(&self) -> Option<&(dyn StdError + 'static)> {
+		match self {
+			Self::Argument(error) => Some(error),
+			Self::Check(failure) => Some(failure),
+			_ => None
+		}
+	}
+}
+
+impl From<ArgError> for CommandError {
+	fn from(error: ArgError) -> Self {
+		Self::Argument(error)
+	}
+}
+
+impl From<ClientError> for CommandError {
+	fn from(error: ClientError) -> Self {
+		Self::Client(error)
+	}
+}
+
+impl From<String> for CommandError {
+	fn from(error: String) -> Self {
+		Self::Other(error)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use self::super::super::{client::Client, context::GatewayState};
+	use serde_json::from_value;
+
+	fn message(author: u64, room: u64, content: &str) -> Message {
+		from_value(serde_json::json!({"content": content, "id": "1",
+			"room_id": room.to_string(), "author_id": author.to_string()})).unwrap()
+	}
+
+	fn context() -> Context {
+		Context::new(Client::new("token"), Arc::new(GatewayState::default()))
+	}
+
+	fn framework() -> Framework {
+		let mut framework = Framework::new();
+		framework.prefix("$").prefix("bot ").help_command("help");
+		framework.command("add", |_, _, _| async {Ok(())})
+			.alias("plus").help("Adds numbers.").usage("<a> <b>");
+		framework.command("ping", |_, _, _| async {Ok(())});
+		framework
+	}
+
+	#[test]
+	fn parse_test() {
+		let (framework, context) = (framework(), context());
+		let parse = |content| framework.parse(&context, content)
+			.map(|(name, args)| (name.to_owned(), args.arguments.into_iter()
+				.map(|(_, argument)| argument).collect()));
+
+		assert_eq!(parse("$add 1  2"),
+			Some(("add".into(), vec!["1".into(), "2".into()])));
+		assert_eq!(parse("bot  ping"), Some(("ping".into(), vec![])));
+		assert_eq!(parse("add 1 2"), None);
+		assert_eq!(parse("<@1> ping"), None);
+		assert_eq!(framework.find_command("plus").unwrap().name(), "add");
+		assert!(framework.find_command("minus").is_none());
+	}
+
+	#[test]
+	fn mention_prefix_test() {
+		let mut framework = framework();
+		let state = GatewayState::default();
+		*state.user.write().unwrap() = Some(from_value(serde_json::json!({
+			"username": "bot", "name": "Bot", "icon": null, "header": null, "id": "1"
+		})).unwrap());
+		let context = Context::new(Client::new("token"), Arc::new(state));
+
+		assert!(framework.parse(&context, "<@1> ping").is_none());
+		framework.mention_prefix(true);
+		assert_eq!(framework.parse(&context, "<@1> ping").unwrap().0, "ping");
+		assert!(framework.parse(&context, "<@2> ping").is_none());
+	}
+
+	#[test]
+	fn args_test() {
+		let mut args = Args::new("1 x <@5> the rest");
+		assert_eq!(args.single::<i64>().unwrap(), 1);
+		assert_eq!(args.optional::<i64>().unwrap_err().to_string(),
+			"invalid argument x, expected i64");
+		assert_eq!(args.single::<String>().unwrap(), "x");
+		assert_eq!(args.user().unwrap(), UserId(5));
+		assert_eq!(args.remaining(), 2);
+		assert_eq!(args.rest(), "the rest");
+		assert_eq!(args.optional::<String>().unwrap(), None);
+		assert_eq!(args.single::<String>().unwrap_err().to_string(),
+			"missing argument, expected String");
+		assert_eq!(short_type_name::<Option<UserId>>(), "Option<UserId>");
+
+		let mut args = Args::new(r#"say "a  b"  c "#);
+		assert_eq!(args.single::<String>().unwrap(), "say");
+		assert_eq!(args.rest(), r#""a  b"  c"#);
+		assert_eq!(args.rest(), "");
+	}
+
+	#[test]
+	fn command_error_test() {
+		let error = CommandError::from(ArgError::Missing("i64".to_owned()));
+		assert_eq!(error.to_string(), "missing argument, expected i64");
+		assert!(error.source().is_some());
+		let error = CommandError::Check(CheckFailure::OwnersOnly);
+		assert_eq!(error.to_string(), "command is for owners only");
+		assert_eq!(CommandError::from("oops".to_owned()).to_string(), "oops");
+	}
+
+	#[test]
+	fn help_test() {
+		let framework = framework();
+		assert_eq!(framework.help_text(None), "add - Adds numbers.\nping");
+		assert_eq!(framework.help_text(Some("plus")),
+			"add <a> <b>\nAdds numbers.\nAliases: plus");
+		assert_eq!(framework.help_text(Some("x")), "There is no command named x.");
+	}
+
+	async fn run_checks(framework: &Framework, name: &str, message: Message) ->
+			Result<(), CheckFailure> {
+		framework.find_command(name).unwrap()
+			.run_checks(&[UserId(1)], &context(), &message).await
+	}
+
+	#[tokio::test]
+	async fn checks_test() {
+		let mut framework = Framework::new();
+		framework.command("house", |_, _, _| async {Ok(())})
+			.allowed_houses(&[HouseId(1)]);
+		framework.command("room", |_, _, _| async {Ok(())})
+			.allowed_rooms(&[RoomId(1)]);
+		framework.command("user", |_, _, _| async {Ok(())})
+			.allowed_users(&[UserId(1)]);
+		framework.command("owner", |_, _, _| async {Ok(())}).owners_only();
+		framework.command("custom", |_, _, _| async {Ok(())})
+			.check(|_, message| async move {
+				match message.content.as_str() {
+					"no" => Err("said no".to_owned()),
+					_ => Ok(())
+				}
+			});
+		let framework = &framework;
+		let run = |name, author, room, content| async move {
+			run_checks(framework, name, message(author, room, content)).await
+		};
+
+		let mut in_house = message(1, 1, "");
+		in_house.house_id = Some(Some(HouseId(1)));
+		assert!(run_checks(framework, "house", in_house).await.is_ok());
+		assert!(matches!(run("house", 1, 1, "").await, Err(CheckFailure::House)));
+		assert!(run("room", 1, 1, "").await.is_ok());
+		assert!(matches!(run("room", 1, 2, "").await, Err(CheckFailure::Room)));
+		assert!(run("user", 1, 1, "").await.is_ok());
+		assert!(matches!(run("user", 2, 1, "").await, Err(CheckFailure::User)));
+		assert!(run("owner", 1, 1, "").await.is_ok());
+		assert!(matches!(run("owner", 2, 1, "").await,
+			Err(CheckFailure::OwnersOnly)));
+		assert!(run("custom", 1, 1, "yes").await.is_ok());
+		match run("custom", 1, 1, "no").await {
+			Err(CheckFailure::Custom(reason)) => assert_eq!(reason, "said no"),
+			result => panic!("expected a custom failure, got {:?}", result)
+		}
+	}
+
+	#[tokio::test]
+	async fn cooldown_test() {
+		let mut framework = Framework::new();
+		framework.command("slow", |_, _, _| async {Ok(())})
+			.user_cooldown(Duration::from_secs(60));
+		framework.command("fast", |_, _, _| async {Ok(())})
+			.user_cooldown(Duration::from_secs(0));
+		let framework = &framework;
+		let run = |name, author| async move {
+			run_checks(framework, name, message(author, 1, "")).await
+		};
+
+		assert!(run("slow", 1).await.is_ok());
+		match run("slow", 1).await {
+			Err(CheckFailure::UserCooldown(left)) =>
+				assert!(left <= Duration::from_secs(60)),
+			result => panic!("expected a user cooldown, got {:?}", result)
+		}
+		assert!(run("slow", 2).await.is_ok());
+
+		for author in 1..=3 {
+			assert!(run("fast", author).await.is_ok());
+			assert!(run("fast", author).await.is_ok());
+		}
+		// Cooldowns that ran out are forgotten, instead of kept forever.
+		let fast = framework.find_command("fast").unwrap();
+		let cooldown = fast.user_cooldown.as_ref().unwrap();
+		assert_eq!(cooldown.last_used.lock().unwrap().len(), 1);
+	}
+
+	#[tokio::test]
+	async fn cooldown_order_test() {
+		let mut framework = Framework::new();
+		framework.command("ping", |_, _, _| async {Ok(())})
+			.user_cooldown(Duration::from_secs(60))
+			.room_cooldown(Duration::from_secs(60));
+		let command = framework.find_command("ping").unwrap();
+		let context = &context();
+
+		let checks = |author, room| async move {
+			command.run_checks(&[], context, &message(author, room, "")).await
+		};
+		assert!(checks(1, 1).await.is_ok());
+		match checks(2, 1).await {
+			Err(CheckFailure::RoomCooldown(_)) => (),
+			result => panic!("expected a room cooldown, got {:?}", result)
+		}
+
+		// The room cooldown stopped the last invocation, so the user's cooldown
+		// must not have been started by it.
+		assert!(checks(2, 2).await.is_ok());
+		match checks(1, 3).await {
+			Err(CheckFailure::UserCooldown(_)) => (),
+			result => panic!("expected a user cooldown, got {:?}", result)
+		}
+	}
+}
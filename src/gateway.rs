@@ -1,13 +1,22 @@
-use self::super::{data::{ClientSettings, House, Message, User}, util::from_str};
+use self::super::{
+	data::{
+		ClientSettings, House, Message, PrivateRoom, Relationship,
+		RelationshipKind, User
+	},
+	id::{HouseId, RoomId, UserId},
+	util::null_default
+};
 use serde::{
 	Deserialize, Serialize,
 	de::{Deserializer, Error as DeserializeError, MapAccess, Unexpected, Visitor},
 	ser::{SerializeMap, Serializer}
 };
+use serde_json::Value as JSONValue;
 use serde_value::Value as UndeserializedAny;
-use std::fmt::{Formatter, Result as FMTResult};
+use std::{collections::HashMap, fmt::{Formatter, Result as FMTResult}};
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Frame {
 	Event(OpCodeEvent),
 	Hello(OpCodeHello),
@@ -64,10 +73,22 @@ impl FrameVisitor {
 			"INIT_STATE" => Ok(OpCodeEvent::InitState(map.next_value()?)),
 			// EventHouseJoin...
 			"HOUSE_JOIN" => Ok(OpCodeEvent::HouseJoin(map.next_value()?)),
+			// EventHouseMemberLeave...
+			"HOUSE_MEMBER_LEAVE" =>
+				Ok(OpCodeEvent::HouseMemberLeave(map.next_value()?)),
 			// EventTypingStart...
 			"TYPING_START" => Ok(OpCodeEvent::TypingStart(map.next_value()?)),
 			// EventMessageCreate...
 			"MESSAGE_CREATE" => Ok(OpCodeEvent::MessageCreate(map.next_value()?)),
+			// EventPrivateRoomCreate...
+			"PRIVATE_ROOM_CREATE" =>
+				Ok(OpCodeEvent::PrivateRoomCreate(map.next_value()?)),
+			// EventPrivateRoomUpdate...
+			"PRIVATE_ROOM_UPDATE" =>
+				Ok(OpCodeEvent::PrivateRoomUpdate(map.next_value()?)),
+			// EventRelationshipUpdate...
+			"RELATIONSHIP_UPDATE" =>
+				Ok(OpCodeEvent::RelationshipUpdate(map.next_value()?)),
 
 			// Invalid event...
 			event => Err(DeserializeError::invalid_value(Unexpected::Str(event),
@@ -176,7 +197,7 @@ impl<'d> Visitor<'d> for FrameVisitor {
 						3 => Frame::HeartBeat,
 
 						// Operation codes that have data...
-						0 | 1 | 2 => return Err(DeserializeError::missing_field("d")),
+						0..=2 => return Err(DeserializeError::missing_field("d")),
 						// Unknown operation code...
 						_ => return Err(DeserializeError::invalid_value(
 							Unexpected::Unsigned(op_code.into()), &"valid opcode"))
@@ -240,43 +261,168 @@ impl<'d> Visitor<'d> for FrameVisitor {
 }
 
 // Automatically serialized and deserialized by Frame.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum OpCodeEvent {
 	InitState(EventInitState),
 	HouseJoin(House),
+	HouseMemberLeave(EventHouseMemberLeave),
 	TypingStart(EventTypingStart),
-	MessageCreate(Message)
+	MessageCreate(Message),
+	PrivateRoomCreate(PrivateRoom),
+	PrivateRoomUpdate(PrivateRoom),
+	RelationshipUpdate(EventRelationshipUpdate)
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct OpCodeHello {
 	#[serde(rename = "hbt_int")]
 	pub heart_beat: u16
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct OpCodeLogin {
 	pub token: String
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct EventInitState {
 	pub user: User,
-	pub settings: ClientSettings
+	pub settings: ClientSettings,
+	#[serde(default, deserialize_with = "null_default")]
+	pub private_rooms: Vec<PrivateRoom>,
+	/// The relationships with other users, by the id of the other user.
+	#[serde(default, deserialize_with = "relationships")]
+	pub relationships: HashMap<UserId, Relationship>
+}
+
+/// Deserializes relationships by the id of the other user, taking the id from
+/// the key when an entry leaves it out, and skipping entries that can't be
+/// read, so one odd relationship doesn't fail the whole initial state.
+fn relationships<'d, D>(deserializer: D) ->
+		Result<HashMap<UserId, Relationship>, D::Error> where D: Deserializer<'d> {
+	let entries: HashMap<String, JSONValue> = null_default(deserializer)?;
+	Ok(entries.into_iter().filter_map(|(key, mut entry)| {
+		if let JSONValue::Object(fields) = &mut entry
+			{fields.entry("user_id").or_insert_with(|| key.clone().into());}
+		let relationship: Relationship = serde_json::from_value(entry).ok()?;
+		Some((key.parse().unwrap_or(relationship.user_id), relationship))
+	}).collect())
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// A member that left a house. Hiven sends this whether the member left by
+/// themselves, was kicked or was banned, and sends no event of it's own for
+/// bans.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EventHouseMemberLeave {
+	pub house_id: HouseId,
+	#[serde(alias = "id")]
+	pub user_id: UserId
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct EventTypingStart {
-	#[serde(deserialize_with = "from_str")]
-	pub room_id: u64,
-	#[serde(rename = "author_id", deserialize_with = "from_str")]
-	pub user_id: u64
+	pub room_id: RoomId,
+	#[serde(rename = "author_id")]
+	pub user_id: UserId
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EventRelationshipUpdate {
+	/// The other user of the relationship.
+	pub recipient_id: UserId,
+	#[serde(default)]
+	pub recipient: Option<User>,
+	#[serde(rename = "type")]
+	pub kind: RelationshipKind
 }
 
 #[cfg(test)]
 mod tests {
+	use super::*;
+	use serde_json::from_str as from_json;
+
 	#[test]
 	fn serilization_test() {
-		
+		let frame: Frame = from_json(r#"{"op": 0, "e": "INIT_STATE", "d": {
+			"user": {"username": "a", "name": "A", "icon": null, "header": null,
+				"id": "1"},
+			"settings": {"theme": "dark"},
+			"relationships": {
+				"2": {"user_id": "2", "type": 3},
+				"3": {"type": 4},
+				"4": {"user_id": "4", "type": "friend"},
+				"5": null
+			}
+		}}"#).unwrap();
+
+		match frame {
+			Frame::Event(OpCodeEvent::InitState(state)) => {
+				let relationships = &state.relationships;
+				assert_eq!(relationships.len(), 2);
+				assert_eq!(relationships[&UserId(2)].kind, RelationshipKind::Friend);
+				assert_eq!(relationships[&UserId(3)].user_id, UserId(3));
+				assert_eq!(relationships[&UserId(3)].kind, RelationshipKind::Blocked);
+			},
+			frame => panic!("unexpected frame {:?}", frame)
+		}
+	}
+
+	#[test]
+	fn relationship_update_test() {
+		let frame: Frame = from_json(r#"{"op": 0, "e": "RELATIONSHIP_UPDATE",
+			"d": {"recipient_id": "2", "type": 2, "recipient": {"username": "b",
+				"name": "B", "icon": null, "header": null, "id": "2"}}}"#).unwrap();
+
+		match frame {
+			Frame::Event(OpCodeEvent::RelationshipUpdate(update)) => {
+				assert_eq!(update.recipient_id, UserId(2));
+				assert_eq!(update.kind, RelationshipKind::IncomingRequest);
+				assert_eq!(update.recipient.unwrap().username, "b");
+			},
+			frame => panic!("unexpected frame {:?}", frame)
+		}
+	}
+
+	#[test]
+	fn private_room_test() {
+		let frame: Frame = from_json(r#"{"op": 0, "e": "PRIVATE_ROOM_CREATE",
+			"d": {"id": "1", "recipients": [{"username": "b", "name": "B",
+				"icon": null, "header": null, "id": "2"}]}}"#).unwrap();
+
+		match frame {
+			Frame::Event(OpCodeEvent::PrivateRoomCreate(room)) =>
+				assert_eq!((room.id, room.is_group()), (RoomId(1), false)),
+			frame => panic!("unexpected frame {:?}", frame)
+		}
+
+		let frame: Frame = from_json(r#"{"op": 0, "e": "PRIVATE_ROOM_UPDATE",
+			"d": {"id": "1", "name": "Group", "owner_id": "2", "recipients": [
+				{"username": "b", "name": "B", "icon": null, "header": null,
+					"id": "2"},
+				{"username": "c", "name": "C", "icon": null, "header": null,
+					"id": "3"}
+			]}}"#).unwrap();
+
+		match frame {
+			Frame::Event(OpCodeEvent::PrivateRoomUpdate(room)) => {
+				assert!(room.is_group());
+				assert_eq!(room.name, Some(Some("Group".to_owned())));
+				assert_eq!(room.owner_id, Some(Some(UserId(2))));
+			},
+			frame => panic!("unexpected frame {:?}", frame)
+		}
+	}
+
+	#[test]
+	fn house_member_leave_test() {
+		let frame: Frame = from_json(r#"{"op": 0, "e": "HOUSE_MEMBER_LEAVE",
+			"d": {"house_id": "1", "id": "2"}}"#).unwrap();
+
+		match frame {
+			Frame::Event(OpCodeEvent::HouseMemberLeave(leave)) =>
+				assert_eq!((leave.house_id, leave.user_id), (HouseId(1), UserId(2))),
+			frame => panic!("unexpected frame {:?}", frame)
+		}
 	}
 }
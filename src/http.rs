@@ -1,5 +1,12 @@
-use reqwest::Method;
+use self::super::{
+	data::ClientSettings,
+	id::{HouseId, MessageId, RoomId, UserId}
+};
+use futures::stream::{Stream, unfold};
+use reqwest::{Body, Method, multipart::Part};
 use serde::{Deserialize, Serialize};
+use std::{io::Result as IOResult, path::Path, pin::Pin};
+use tokio::{fs::File, io::{AsyncRead, AsyncReadExt}};
 
 pub struct RequestInfo {
 	pub path: PathInfo,
@@ -9,14 +16,45 @@ pub struct RequestInfo {
 
 pub enum PathInfo {
 	MessageSend {
-		channel_id: u64
+		channel_id: RoomId
+	},
+	MediaMessageSend {
+		channel_id: RoomId
 	},
 	MessageEditDelete {
-		channel_id: u64,
-		message_id: u64
+		channel_id: RoomId,
+		message_id: MessageId
 	},
 	TypingTrigger {
-		channel_id: u64
+		channel_id: RoomId
+	},
+	PrivateRooms,
+	/// A user, by either their id or their username, which is percent encoded.
+	User {
+		user: String
+	},
+	CurrentUser,
+	Settings,
+	FriendRequests,
+	FriendRequest {
+		user_id: UserId
+	},
+	Friend {
+		user_id: UserId
+	},
+	Block {
+		user_id: UserId
+	},
+	Member {
+		house_id: HouseId,
+		user_id: UserId
+	},
+	Bans {
+		house_id: HouseId
+	},
+	Ban {
+		house_id: HouseId,
+		user_id: UserId
 	}
 }
 
@@ -25,33 +63,187 @@ impl PathInfo {
 		match self {
 			Self::MessageSend {channel_id} =>
 				format!("/rooms/{}/messages", channel_id),
+			Self::MediaMessageSend {channel_id} =>
+				format!("/rooms/{}/media_messages", channel_id),
 			Self::MessageEditDelete {channel_id, message_id, ..} =>
 				format!("/rooms/{}/messages/{}", channel_id, message_id),
 			Self::TypingTrigger {channel_id} =>
-				format!("/rooms/{}/typing", channel_id)
+				format!("/rooms/{}/typing", channel_id),
+			Self::PrivateRooms => "/users/@me/rooms".to_owned(),
+			Self::User {user} => format!("/users/{}", encode_segment(user)),
+			Self::CurrentUser => "/users/@me".to_owned(),
+			Self::Settings => "/users/@me/settings".to_owned(),
+			Self::FriendRequests =>
+				"/relationships/@me/friend-requests".to_owned(),
+			Self::FriendRequest {user_id} =>
+				format!("/relationships/@me/friend-requests/{}", user_id),
+			Self::Friend {user_id} =>
+				format!("/relationships/@me/friends/{}", user_id),
+			Self::Block {user_id} =>
+				format!("/relationships/@me/blocked/{}", user_id),
+			Self::Member {house_id, user_id} =>
+				format!("/houses/{}/members/{}", house_id, user_id),
+			Self::Bans {house_id} => format!("/houses/{}/bans", house_id),
+			Self::Ban {house_id, user_id} =>
+				format!("/houses/{}/bans/{}", house_id, user_id)
+		}
+	}
+}
+
+/// Percent encodes every byte of a path segment that isn't unreserved.
+fn encode_segment(segment: &str) -> String {
+	let mut encoded = String::with_capacity(segment.len());
+	for byte in segment.bytes() {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' =>
+				encoded.push(byte as char),
+			byte => encoded.push_str(&format!("%{:02X}", byte))
 		}
 	}
+	encoded
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum RequestBodyInfo {
 	MessageSend {
-		content: String
+		content: String,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		reply_to_id: Option<MessageId>
 	},
 	MessageDelete,
-	TypingTrigger {}
+	TypingTrigger {},
+	PrivateRoomCreate {
+		recipient: UserId
+	},
+	GroupRoomCreate {
+		recipients: Vec<UserId>
+	},
+	ProfileEdit {
+		#[serde(skip_serializing_if = "Option::is_none")]
+		name: Option<String>,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		icon: Option<String>,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		header: Option<String>,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		bio: Option<String>
+	},
+	SettingsUpdate(ClientSettings),
+	FriendRequestSend {
+		user_id: UserId
+	},
+	UserBlock,
+	RelationshipDelete,
+	MemberKick,
+	MemberBan {
+		#[serde(skip_serializing_if = "Option::is_none")]
+		reason: Option<String>
+	},
+	BanDelete,
+	Get
 }
 
 impl RequestBodyInfo {
 	pub fn method(&self) -> Method {
 		match self {
-			Self::MessageSend {..} | Self::TypingTrigger {} => Method::POST,
-			Self::MessageDelete => Method::DELETE
+			Self::MessageSend {..} | Self::TypingTrigger {} |
+				Self::PrivateRoomCreate {..} | Self::GroupRoomCreate {..} |
+				Self::FriendRequestSend {..} => Method::POST,
+			Self::ProfileEdit {..} | Self::SettingsUpdate(_) => Method::PATCH,
+			Self::UserBlock | Self::MemberBan {..} => Method::PUT,
+			Self::MessageDelete | Self::RelationshipDelete | Self::MemberKick |
+				Self::BanDelete => Method::DELETE,
+			Self::Get => Method::GET
 		}
 	}
 }
 
+/// The body of a successful response, wrapping the data that was requested.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ResponseInfo<T> {
+	pub data: T
+}
+
+/// A file to upload along with a message.
+/// ```rust
+/// use hiven_rs::{Client, http::FileUpload, id::RoomId};
+///
+/// # async fn upload(client: Client, room: RoomId) -> std::io::Result<()> {
+/// let screenshot = FileUpload::from_path("screenshot.png").await?;
+/// let log = FileUpload::from_bytes("build.log", "Build succeeded.");
+///
+/// client.send_files(room, "Build #42", vec![screenshot, log]).await.unwrap();
+/// # Ok(())
+/// # }
+/// ```
+pub struct FileUpload {
+	name: String,
+	body: FileBody
+}
+
+enum FileBody {
+	Bytes(Vec<u8>),
+	Reader(Pin<Box<dyn AsyncRead + Send + Sync>>)
+}
+
+impl FileUpload {
+	/// Creates an upload of a file with the given name and contents.
+	pub fn from_bytes<N, B>(name: N, bytes: B) -> Self
+			where N: Into<String>, B: Into<Vec<u8>> {
+		Self {name: name.into(), body: FileBody::Bytes(bytes.into())}
+	}
+
+	/// Creates an upload of the file at the given path, named after the file.
+	/// The file is opened now, but only read while it's being uploaded.
+	pub async fn from_path<P>(path: P) -> IOResult<Self>
+			where P: AsRef<Path> {
+		let path = path.as_ref();
+		let name = path.file_name().unwrap_or_default().to_string_lossy();
+		Ok(Self::from_reader(name, File::open(path).await?))
+	}
+
+	/// Creates an upload of a file with the given name, streaming it's contents
+	/// from the reader while it's being uploaded.
+	pub fn from_reader<N, R>(name: N, reader: R) -> Self
+			where N: Into<String>, R: AsyncRead + Send + Sync + 'static {
+		Self {name: name.into(), body: FileBody::Reader(Box::pin(reader))}
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub(crate) fn into_part(self) -> Part {
+		let mime = mime_guess::from_path(&self.name).first_or_octet_stream();
+		let part = match self.body {
+			FileBody::Bytes(bytes) => Part::bytes(bytes),
+			FileBody::Reader(reader) =>
+				Part::stream(Body::wrap_stream(read_chunks(reader)))
+		};
+
+		// A guessed mime type is always valid.
+		part.file_name(self.name).mime_str(mime.as_ref()).unwrap()
+	}
+}
+
+/// Reads a reader in chunks, until it's end or an error.
+fn read_chunks(reader: Pin<Box<dyn AsyncRead + Send + Sync>>) ->
+		impl Stream<Item = IOResult<Vec<u8>>> + Send + Sync + 'static {
+	unfold(Some(reader), |reader| async move {
+		let mut reader = reader?;
+		let mut chunk = vec![0; 8192];
+		match reader.read(&mut chunk).await {
+			Ok(0) => None,
+			Ok(length) => {
+				chunk.truncate(length);
+				Some((Ok(chunk), Some(reader)))
+			},
+			Err(error) => Some((Err(error), None))
+		}
+	})
+}
+
 /*pub enum RequestInfo {
 	MessageSend {
 		channel_id: u64,
@@ -87,3 +279,91 @@ impl RequestInfo {
 		}
 	}
 }*/
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::stream::StreamExt;
+	use std::{
+		io::Error as IOError,
+		task::{Context as TaskContext, Poll}
+	};
+
+	/// A reader that fails every read.
+	struct Failing;
+
+	impl AsyncRead for Failing {
+		fn poll_read(self: Pin<&mut Self>, _context: &mut TaskContext,
+				_buffer: &mut [u8]) -> Poll<IOResult<usize>> {
+			Poll::Ready(Err(IOError::other("read failed")))
+		}
+	}
+
+	#[tokio::test]
+	async fn read_chunks_test() {
+		let bytes: &'static [u8] = &[7; 10000];
+		let chunks: Vec<_> = read_chunks(Box::pin(bytes)).collect().await;
+		let lengths: Vec<_> = chunks.into_iter()
+			.map(|chunk| chunk.unwrap().len()).collect();
+		assert_eq!(lengths, [8192, 1808]);
+
+		let empty: &'static [u8] = &[];
+		assert_eq!(read_chunks(Box::pin(empty)).collect::<Vec<_>>().await.len(), 0);
+
+		let failing = Box::pin(AsyncReadExt::chain(&[1, 2, 3][..], Failing));
+		let mut chunks = Box::pin(read_chunks(failing));
+		assert_eq!(chunks.next().await.unwrap().unwrap(), [1, 2, 3]);
+		assert!(chunks.next().await.unwrap().is_err());
+		assert!(chunks.next().await.is_none());
+	}
+
+	#[test]
+	fn user_path_test() {
+		let path = PathInfo::User {user: UserId(1).to_string()};
+		assert_eq!(path.path(), "/users/1");
+		let path = PathInfo::User {user: "some_one/../@me?x=ü".to_owned()};
+		assert_eq!(path.path(), "/users/some_one%2F..%2F%40me%3Fx%3D%C3%BC");
+		assert_eq!(PathInfo::CurrentUser.path(), "/users/@me");
+
+		let edit = RequestBodyInfo::ProfileEdit {
+			name: None, icon: None, header: None, bio: Some("Hi!".to_owned())
+		};
+		assert_eq!(edit.method(), Method::PATCH);
+		assert_eq!(serde_json::to_string(&edit).unwrap(), r#"{"bio":"Hi!"}"#);
+		assert_eq!(RequestBodyInfo::Get.method(), Method::GET);
+	}
+
+	#[test]
+	fn private_rooms_path_test() {
+		assert_eq!(PathInfo::PrivateRooms.path(), "/users/@me/rooms");
+
+		let body = RequestBodyInfo::PrivateRoomCreate {recipient: UserId(1)};
+		assert_eq!(body.method(), Method::POST);
+		assert_eq!(serde_json::to_string(&body).unwrap(), r#"{"recipient":"1"}"#);
+
+		let recipients = vec![UserId(1), UserId(2)];
+		let body = RequestBodyInfo::GroupRoomCreate {recipients};
+		assert_eq!(body.method(), Method::POST);
+		assert_eq!(serde_json::to_string(&body).unwrap(),
+			r#"{"recipients":["1","2"]}"#);
+	}
+
+	#[test]
+	fn member_paths_test() {
+		let (house_id, user_id) = (HouseId(1), UserId(2));
+		let kick = PathInfo::Member {house_id, user_id};
+		assert_eq!(kick.path(), "/houses/1/members/2");
+		assert_eq!(RequestBodyInfo::MemberKick.method(), Method::DELETE);
+
+		let ban = PathInfo::Ban {house_id, user_id};
+		assert_eq!(ban.path(), "/houses/1/bans/2");
+		let body = RequestBodyInfo::MemberBan {reason: Some("spam".to_owned())};
+		assert_eq!(body.method(), Method::PUT);
+		assert_eq!(serde_json::to_string(&body).unwrap(), r#"{"reason":"spam"}"#);
+		let body = RequestBodyInfo::MemberBan {reason: None};
+		assert_eq!(serde_json::to_string(&body).unwrap(), "{}");
+		assert_eq!(RequestBodyInfo::BanDelete.method(), Method::DELETE);
+
+		assert_eq!(PathInfo::Bans {house_id}.path(), "/houses/1/bans");
+	}
+}
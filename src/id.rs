@@ -0,0 +1,137 @@
+use serde::{
+	Deserialize, Serialize,
+	de::{Deserializer, Error as DeserializeError, Unexpected, Visitor},
+	ser::Serializer
+};
+use std::{
+	fmt::{Display, Formatter, Result as FMTResult},
+	num::ParseIntError,
+	str::FromStr,
+	time::{Duration, SystemTime, UNIX_EPOCH}
+};
+
+/// The moment hiven's snowflakes count time from, 2019-07-08T00:00:00Z, in
+/// milliseconds since the unix epoch.
+pub const HIVEN_EPOCH: u64 = 1_562_544_000_000;
+
+/// Declares a snowflake id newtype, that serializes as a string.
+// This is a macro_rules! macro, because the hygiene of decl_macro would hide
+// the id's field from everything outside of this macro.
+macro_rules! snowflake {($(#[$attribute:meta])* $name:ident) => {
+	$(#[$attribute])*
+	#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+	pub struct $name(pub u64);
+
+	impl $name {
+		/// The moment this id was created, encoded in it's snowflake.
+		pub fn created_at(&self) -> SystemTime {
+			snowflake_time(self.0)
+		}
+	}
+
+	impl Display for $name {
+		fn fmt(&self, formatter: &mut Formatter) -> FMTResult {
+			Display::fmt(&self.0, formatter)
+		}
+	}
+
+	impl FromStr for $name {
+		type Err = ParseIntError;
+
+		fn from_str(string: &str) -> Result<Self, ParseIntError> {
+			u64::from_str(string).map(Self)
+		}
+	}
+
+	impl From<u64> for $name {
+		fn from(id: u64) -> Self {
+			Self(id)
+		}
+	}
+
+	impl From<$name> for u64 {
+		fn from(id: $name) -> u64 {
+			id.0
+		}
+	}
+
+	impl Serialize for $name {
+		fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+				where S: Serializer {
+			serializer.collect_str(&self.0)
+		}
+	}
+
+	impl<'d> Deserialize<'d> for $name {
+		fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+				where D: Deserializer<'d> {
+			deserializer.deserialize_any(SnowflakeVisitor).map(Self)
+		}
+	}
+}}
+
+snowflake!(
+	/// The id of a [House](../data/struct.House.html).
+	HouseId
+);
+snowflake!(
+	/// The id of a [Room](../data/struct.Room.html).
+	RoomId
+);
+snowflake!(
+	/// The id of a [Message](../data/struct.Message.html).
+	MessageId
+);
+snowflake!(
+	/// The id of a [User](../data/struct.User.html).
+	UserId
+);
+
+/// Extracts the creation time encoded in a snowflake.
+fn snowflake_time(snowflake: u64) -> SystemTime {
+	UNIX_EPOCH + Duration::from_millis((snowflake >> 22) + HIVEN_EPOCH)
+}
+
+/// Deserializes snowflakes, which hiven sends as strings, from either strings
+/// or integers.
+struct SnowflakeVisitor;
+
+impl<'d> Visitor<'d> for SnowflakeVisitor {
+	type Value = u64;
+
+	fn expecting(&self, formatter: &mut Formatter) -> FMTResult {
+		write!(formatter, "a snowflake, as a string or an integer")
+	}
+
+	fn visit_u64<E>(self, value: u64) -> Result<u64, E>
+			where E: DeserializeError {
+		Ok(value)
+	}
+
+	fn visit_str<E>(self, value: &str) -> Result<u64, E>
+			where E: DeserializeError {
+		u64::from_str(value).map_err(|_|
+			E::invalid_value(Unexpected::Str(value), &self))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::{from_str as from_json, to_string as to_json};
+
+	#[test]
+	fn serialization_test() {
+		let id: RoomId = from_json("\"175036727902074880\"").unwrap();
+		assert_eq!(id, RoomId(175036727902074880));
+		assert_eq!(from_json::<RoomId>("175036727902074880").unwrap(), id);
+		assert_eq!(to_json(&id).unwrap(), "\"175036727902074880\"");
+		assert_eq!("175036727902074880".parse(), Ok(id));
+	}
+
+	#[test]
+	fn timestamp_test() {
+		let created_at = UserId(1 << 22).created_at();
+		assert_eq!(created_at, UNIX_EPOCH + Duration::from_millis(HIVEN_EPOCH + 1));
+	}
+}
@@ -1,8 +1,20 @@
-#![feature(decl_macro, try_blocks)]
+#![feature(decl_macro)]
+pub mod builder;
+pub mod cache;
 pub mod client;
+pub mod collector;
+pub mod context;
 pub mod data;
+pub mod framework;
 pub mod gateway;
 pub mod http;
+pub mod id;
+pub mod middleware;
+pub mod parse;
 mod util;
 
-pub use self::client::{Client, EventHandler, GateKeeper};
+pub use self::{
+	client::{Client, EventHandler, GateKeeper},
+	context::Context
+};
+pub use async_trait::async_trait;
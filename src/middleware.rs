@@ -0,0 +1,90 @@
+use self::super::{context::Context, gateway::OpCodeEvent};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use std::sync::Arc;
+
+pub(crate) type Endpoint<'m> =
+	dyn Fn(Context, OpCodeEvent) -> BoxFuture<'m, ()> + Send + Sync + 'm;
+
+/// Middleware wraps the dispatch of every event of a [GateKeeper].
+///
+/// Each piece of middleware is given the event, and the rest of the chain as
+/// [Next]. Calling [Next::run] passes the event on to the next piece of
+/// middleware, and eventually to the event handler, listeners and framework.
+/// Not calling it short circuits dispatch, dropping the event. Middleware is
+/// ran in the order it was added in.
+/// ```rust
+/// use hiven_rs::{
+///     async_trait, context::Context, gateway::OpCodeEvent,
+///     middleware::{Middleware, Next}
+/// };
+/// use std::time::Instant;
+///
+/// struct Timer;
+///
+/// #[async_trait]
+/// impl Middleware for Timer {
+///     async fn handle(&self, context: Context, event: OpCodeEvent,
+///             next: Next<'_>) {
+///         let start = Instant::now();
+///         next.run(context, event).await;
+///         println!("Event handled in {:?}.", start.elapsed());
+///     }
+/// }
+/// ```
+///
+/// [GateKeeper]: ../client/struct.GateKeeper.html
+/// [Next]: struct.Next.html
+/// [Next::run]: struct.Next.html#method.run
+#[async_trait]
+pub trait Middleware: Send + Sync {
+	async fn handle(&self, context: Context, event: OpCodeEvent, next: Next<'_>);
+}
+
+/// The rest of a middleware chain.
+pub struct Next<'m> {
+	middleware: &'m [Arc<dyn Middleware>],
+	endpoint: &'m Endpoint<'m>
+}
+
+impl<'m> Next<'m> {
+	pub(crate) fn new(middleware: &'m [Arc<dyn Middleware>],
+			endpoint: &'m Endpoint<'m>) -> Self {
+		Self {middleware, endpoint}
+	}
+
+	/// Passes the event on to the rest of the chain.
+	pub async fn run(self, context: Context, event: OpCodeEvent) {
+		match self.middleware.split_first() {
+			Some((middleware, rest)) => {
+				let next = Self {middleware: rest, endpoint: self.endpoint};
+				middleware.handle(context, event, next).await
+			},
+			None => (self.endpoint)(context, event).await
+		}
+	}
+}
+
+/// Middleware that only lets events matching a predicate through.
+pub struct Filter<F>
+		where F: Fn(&Context, &OpCodeEvent) -> bool + Send + Sync {
+	predicate: F
+}
+
+impl<F> Filter<F>
+		where F: Fn(&Context, &OpCodeEvent) -> bool + Send + Sync {
+	pub fn new(predicate: F) -> Self {
+		Self {predicate}
+	}
+}
+
+#[async_trait]
+impl<F> Middleware for Filter<F>
+		where F: Fn(&Context, &OpCodeEvent) -> bool + Send + Sync {
+	async fn handle(&self, context: Context, event: OpCodeEvent,
+			next: Next<'_>) {
+		if (self.predicate)(&context, &event) {
+			next.run(context, event).await
+		}
+	}
+}
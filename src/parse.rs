@@ -0,0 +1,190 @@
+use self::super::id::{RoomId, UserId};
+use std::str::FromStr;
+
+/// The characters hiven gives a meaning to in markdown.
+const MARKDOWN: &[char] = &['\\', '*', '_', '~', '`', '|', '>'];
+
+/// Parses a mention of a user, such as `<@175036727902074880>`.
+pub fn parse_user_mention(mention: &str) -> Option<UserId> {
+	parse_mention(mention, "<@")
+}
+
+/// Parses a mention of a room, such as `<#175036727902074880>`.
+pub fn parse_room_mention(mention: &str) -> Option<RoomId> {
+	parse_mention(mention, "<#")
+}
+
+/// Finds every user mentioned in the content of a message, in the order they
+/// were mentioned in.
+pub fn user_mentions(content: &str) -> Vec<UserId> {
+	find_mentions(content, "<@")
+}
+
+/// Finds every room mentioned in the content of a message, in the order they
+/// were mentioned in.
+pub fn room_mentions(content: &str) -> Vec<RoomId> {
+	find_mentions(content, "<#")
+}
+
+fn parse_mention<T>(mention: &str, prefix: &str) -> Option<T>
+		where T: FromStr {
+	let id = mention.strip_prefix(prefix)?.strip_suffix('>')?;
+	match id.bytes().all(|byte| byte.is_ascii_digit()) {
+		true => id.parse().ok(),
+		false => None
+	}
+}
+
+fn find_mentions<T>(content: &str, prefix: &str) -> Vec<T>
+		where T: FromStr {
+	content.match_indices(prefix).filter_map(|(start, _)| {
+		let end = start + content[start..].find('>')?;
+		parse_mention(&content[start..=end], prefix)
+	}).collect()
+}
+
+/// Escapes every markdown character in the text, so it's shown as is.
+/// ```rust
+/// use hiven_rs::parse::escape_markdown;
+///
+/// assert_eq!(escape_markdown("*not bold*"), "\\*not bold\\*");
+/// ```
+pub fn escape_markdown(text: &str) -> String {
+	let characters: Vec<char> = text.chars().collect();
+	let mut escaped = String::with_capacity(text.len());
+	for (index, &character) in characters.iter().enumerate() {
+		if character == '\\' || is_markdown(&characters, index)
+			{escaped.push('\\')}
+		escaped.push(character);
+	}
+	escaped
+}
+
+/// Removes every markdown character from the text, leaving only what would be
+/// shown. Escaped markdown characters are kept, without their escapes.
+/// ```rust
+/// use hiven_rs::parse::strip_markdown;
+///
+/// assert_eq!(strip_markdown("**bold** and \\*not\\*"), "bold and *not*");
+/// assert_eq!(strip_markdown("> ping <@1> about my_var, 2 > 1"),
+///     "ping <@1> about my_var, 2 > 1");
+/// ```
+pub fn strip_markdown(text: &str) -> String {
+	let characters: Vec<char> = text.chars().collect();
+	let mut stripped = String::with_capacity(text.len());
+	let mut index = 0;
+	while index < characters.len() {
+		let character = characters[index];
+		match characters.get(index + 1) {
+			Some(&escaped) if character == '\\' && MARKDOWN.contains(&escaped) => {
+				stripped.push(escaped);
+				index += 1;
+			},
+			// The space after the > of a quote isn't shown either.
+			Some(' ') if character == '>' && is_markdown(&characters, index) =>
+				index += 1,
+			_ if is_markdown(&characters, index) => (),
+			_ => stripped.push(character)
+		}
+		index += 1;
+	}
+	stripped
+}
+
+/// Whether the character at the index has a meaning in markdown. A > only
+/// quotes at the start of a line, an _ has no meaning within a word and | and ~
+/// only have one when doubled, so mentions and most text are left alone.
+fn is_markdown(characters: &[char], index: usize) -> bool {
+	let character = characters[index];
+	let previous = index.checked_sub(1).map(|index| characters[index]);
+	let next = characters.get(index + 1).copied();
+	let in_word = |character: Option<char>|
+		character.is_some_and(char::is_alphanumeric);
+
+	match character {
+		'*' | '`' => true,
+		'_' => !(in_word(previous) && in_word(next)),
+		'|' | '~' => previous == Some(character) || next == Some(character),
+		'>' => characters[..index].iter().rev()
+			.take_while(|&&character| character != '\n')
+			.all(|character| character.is_whitespace()),
+		_ => false
+	}
+}
+
+/// Splits text into whitespace separated tokens, keeping text in double quotes
+/// together as one token. Quotes can be escaped with a backslash, and a quote
+/// that is never closed runs until the end of the text.
+/// ```rust
+/// use hiven_rs::parse::tokenize;
+///
+/// assert_eq!(tokenize(r#"ban "Some User" for \"spam\""#),
+///     ["ban", "Some User", "for", "\"spam\""]);
+/// ```
+pub fn tokenize(text: &str) -> Vec<String> {
+	tokenize_spans(text).into_iter().map(|(_, token)| token).collect()
+}
+
+/// Splits text into tokens like [tokenize] does, along with the byte index
+/// each token starts at in the text.
+///
+/// [tokenize]: fn.tokenize.html
+pub(crate) fn tokenize_spans(text: &str) -> Vec<(usize, String)> {
+	let mut tokens = Vec::new();
+	let mut token: Option<(usize, String)> = None;
+	let mut quoted = false;
+
+	let mut characters = text.char_indices().peekable();
+	while let Some((index, character)) = characters.next() {
+		let start = || (index, String::new());
+		match character {
+			'\\' if matches!(characters.peek(), Some((_, '"'))) =>
+				token.get_or_insert_with(start).1.push(characters.next().unwrap().1),
+			'"' => {
+				if !quoted {token.get_or_insert_with(start);}
+				quoted = !quoted;
+			},
+			character if character.is_whitespace() && !quoted =>
+				tokens.extend(token.take()),
+			character => token.get_or_insert_with(start).1.push(character)
+		}
+	}
+
+	tokens.extend(token);
+	tokens
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn mention_test() {
+		let content = "<@1> and <@2>, but not <@x> or <#3>";
+		assert_eq!(user_mentions(content), [UserId(1), UserId(2)]);
+		assert_eq!(room_mentions(content), [RoomId(3)]);
+		assert_eq!(parse_user_mention("<@4>"), Some(UserId(4)));
+		assert_eq!(parse_user_mention("<@4> "), None);
+	}
+
+	#[test]
+	fn tokenize_test() {
+		assert_eq!(tokenize("  a  \"b c\" \"\" \"d"), ["a", "b c", "", "d"]);
+		assert_eq!(tokenize("a\"b c\"d"), ["ab cd"]);
+		assert_eq!(tokenize_spans(" ab  \"c\""),
+			[(1, "ab".to_owned()), (5, "c".to_owned())]);
+	}
+
+	#[test]
+	fn markdown_test() {
+		let text = "ping <@123> about my_var, 2 > 1 | 0";
+		assert_eq!(strip_markdown(text), text);
+		assert_eq!(escape_markdown(text), text);
+
+		assert_eq!(strip_markdown("> _quoted_ ~~gone~~\n  >nested ||spoiler||"),
+			"quoted gone\n  nested spoiler");
+		assert_eq!(strip_markdown(r"\_a\ b"), r"_a\ b");
+		assert_eq!(escape_markdown(r"> *a* __b__ \"),
+			r"\> \*a\* \_\_b\_\_ \\");
+	}
+}
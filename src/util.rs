@@ -1,30 +1,21 @@
-use serde::{
-	Deserialize,
-	de::{Deserializer, Error as DeserializeError, Unexpected}
-};
+use serde::{Deserialize, Deserializer};
 use std::sync::Mutex;
 use tokio::join;
 
-const FROM_STR_ERR: &str =
-	"string value that can be parsed into other values";
-
-pub(crate) fn from_str<'d, T, D>(deserializer: D) -> Result<T, D::Error>
-		where T: std::str::FromStr,
+/// Deserializes a value that may be null into it's default when it is.
+pub(crate) fn null_default<'d, T, D>(deserializer: D) -> Result<T, D::Error>
+		where T: Default + Deserialize<'d>,
 			D: Deserializer<'d> {
-	let string = <&'d str>::deserialize(deserializer)?;
-	T::from_str(&string).map_err(|_| DeserializeError::invalid_value(
-		Unexpected::Str(string), &FROM_STR_ERR))
+	Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
 }
 
-pub(crate) fn from_str_opt<'d, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
-		where T: std::str::FromStr,
+/// Deserializes a field that may be left out or null, keeping the two apart.
+/// Used with `#[serde(default)]`, a field that was left out is `None`, while a
+/// null field is `Some(None)`.
+pub(crate) fn double_option<'d, T, D>(deserializer: D) ->
+		Result<Option<Option<T>>, D::Error> where T: Deserialize<'d>,
 			D: Deserializer<'d> {
-	let string = Option::<&'d str>::deserialize(deserializer)?;
-	Ok(match string {
-		Some(string) => Some(T::from_str(&string).map_err(|_| DeserializeError
-			::invalid_value(Unexpected::Str(string), &FROM_STR_ERR))?),
-		None => None
-	})
+	Option::<T>::deserialize(deserializer).map(Some)
 }
 
 pub(crate) macro join_first($($future:expr),*) {{